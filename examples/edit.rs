@@ -1,4 +1,5 @@
 use rust_rocket::client::{Event, RocketClient};
+use rust_rocket::formats::bundle;
 use std::error::Error;
 use std::fs::OpenOptions;
 use std::time::Duration;
@@ -43,11 +44,12 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .truncate(true)
                         .open(TRACKS_FILE)?;
 
-                    // Serialize tracks into the file using bincode
-                    bincode::serialize_into(file, &tracks)?;
+                    // Write tracks into the file as a bundle
+                    bundle::write(file, &tracks)?;
                     // See examples/play.rs for deserializing and playback
                     println!("Tracks saved to {}", TRACKS_FILE);
                 }
+                _ => (),
             }
             println!("{:?}", event);
         }
@@ -1,3 +1,6 @@
+use rust_rocket::formats::bundle;
+use rust_rocket::playback::Playback;
+use rust_rocket::timing::Timing;
 use rust_rocket::RocketPlayer;
 use std::error::Error;
 use std::fs::File;
@@ -6,19 +9,23 @@ use std::time::Duration;
 static TRACKS_FILE: &str = "tracks.bin";
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let rocket = {
+    // Advance rows in real time at 120 BPM and 8 rows per beat
+    let mut playback = Playback::new(Timing::new(120., 8));
+    playback.play();
+
+    let mut rocket = {
         // Open previously saved file (see examples/edit.rs)
         let file = File::open(TRACKS_FILE)?;
-        // Deserialize from the file into Vec<Track> using bincode
-        let tracks = bincode::deserialize_from(file)?;
+        // Read the bundle from the file into Vec<Track>
+        let tracks = bundle::read(file)?;
         // Construct a new read-only, offline RocketPlayer
-        RocketPlayer::new(tracks)
+        RocketPlayer::with_playback(tracks, playback)
     };
     println!("Tracks loaded from {}", TRACKS_FILE);
 
-    let mut current_row = 0;
-
     loop {
+        rocket.update();
+        let current_row = rocket.playback().get_row();
         println!(
             "value: {:?} (row: {:?})",
             rocket
@@ -28,7 +35,6 @@ fn main() -> Result<(), Box<dyn Error>> {
             current_row
         );
 
-        current_row += 1;
         std::thread::sleep(Duration::from_millis(32));
     }
 }
@@ -0,0 +1,54 @@
+/*
+ * C API of rust-rocket, compatible with the sync.h of the reference C librocket.
+ *
+ * Build the library with
+ *     cargo rustc --release --features capi --crate-type staticlib
+ * or with --crate-type cdylib, and link it instead of librocket.
+ *
+ * Unlike in librocket, struct sync_track is opaque, so code reading its name, keys or
+ * num_keys fields doesn't compile. Use sync_get_val instead. sync_set_io_cb is not
+ * supported, track files are always read and written with the standard library.
+ *
+ * The same library works with and without SYNC_PLAYER. Track names and the device base path
+ * must be UTF-8.
+ */
+#ifndef SYNC_H
+#define SYNC_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+struct sync_device;
+struct sync_track;
+
+/* Create a device. Track files named <base>_<track name>.track are loaded for playback. */
+struct sync_device *sync_create_device(const char *base);
+/* Destroy a device and its tracks. */
+void sync_destroy_device(struct sync_device *d);
+
+#ifndef SYNC_PLAYER
+struct sync_cb {
+	void (*pause)(void *, int);
+	void (*set_row)(void *, int);
+	int (*is_playing)(void *);
+};
+#define SYNC_DEFAULT_PORT 1338
+/* Connect to a tracker. Returns 0 on success and -1 on failure. */
+int sync_tcp_connect(struct sync_device *d, const char *host, unsigned short port);
+/* Process tracker commands. Returns -1 if the device is not connected or was disconnected. */
+int sync_update(struct sync_device *d, int row, struct sync_cb *cb, void *cb_param);
+/* Save all tracks to track files. Returns 0 on success and -1 on failure. */
+int sync_save_tracks(const struct sync_device *d);
+#endif /* !defined(SYNC_PLAYER) */
+
+/* Get a track by name. The track lives as long as the device. */
+const struct sync_track *sync_get_track(struct sync_device *d, const char *name);
+/* Get the value of a track at a row. */
+double sync_get_val(const struct sync_track *t, double row);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SYNC_H */
@@ -0,0 +1,260 @@
+//! This module contains an async client for tokio, including the [`AsyncRocketClient`] type.
+//!
+//! This module requires the `tokio` feature.
+use crate::client::{apply_command, next_command};
+use crate::client::{Error, Event, UnknownCommandHook};
+use crate::protocol::{ClientCommand, Command, Decoder, ServerCommand};
+use crate::protocol::{CLIENT_GREETING, SERVER_GREETING};
+use crate::track::Track;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+#[derive(Debug)]
+/// The `AsyncRocketClient` type. This is the async counterpart to
+/// [`RocketClient`](crate::RocketClient).
+///
+/// The client is generic over its stream, which is a tokio [`TcpStream`] by default.
+pub struct AsyncRocketClient<S = TcpStream> {
+    stream: S,
+    decoder: Decoder<ServerCommand>,
+    tracks: Vec<Track>,
+    unknown_command_hook: Option<UnknownCommandHook>,
+    key_events: bool,
+}
+
+impl AsyncRocketClient<TcpStream> {
+    /// Construct a new AsyncRocketClient.
+    ///
+    /// This constructs a new Rocket client and connects to localhost on port 1338.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Connect`] if connection cannot be established, or [`Error::Handshake`]
+    /// if the handshake fails.
+    pub async fn new() -> Result<Self, Error> {
+        Self::connect("localhost", 1338).await
+    }
+
+    /// Construct a new AsyncRocketClient.
+    ///
+    /// This constructs a new Rocket client and connects to a specified host and port.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Connect`] if connection cannot be established, or [`Error::Handshake`]
+    /// if the handshake fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_rocket::async_client::AsyncRocketClient;
+    /// # async fn f() {
+    /// let mut rocket = AsyncRocketClient::connect("localhost", 1338).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn connect(host: &str, port: u16) -> Result<Self, Error> {
+        let stream = TcpStream::connect((host, port))
+            .await
+            .map_err(Error::Connect)?;
+        Self::with_transport(stream).await
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRocketClient<S> {
+    /// Construct a new AsyncRocketClient over an already connected stream.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Handshake`] if the handshake fails.
+    pub async fn with_transport(stream: S) -> Result<Self, Error> {
+        let mut rocket = Self {
+            stream,
+            decoder: Decoder::new(),
+            tracks: Vec::new(),
+            unknown_command_hook: None,
+            key_events: false,
+        };
+
+        rocket.handshake().await?;
+
+        Ok(rocket)
+    }
+
+    /// Get track by name.
+    ///
+    /// If the track does not yet exist it will be created.
+    ///
+    /// # Errors
+    ///
+    /// This method can return an [`Error::IOError`] if Rocket tracker disconnects.
+    pub async fn get_track_mut(&mut self, name: &str) -> Result<&mut Track, Error> {
+        if let Some(i) = self.tracks.iter().position(|t| t.get_name() == name) {
+            Ok(&mut self.tracks[i])
+        } else {
+            // Send GET_TRACK message
+            let mut buf = Vec::new();
+            ClientCommand::GetTrack(name.to_owned()).encode(&mut buf);
+            self.stream.write_all(&buf).await.map_err(Error::IOError)?;
+
+            self.tracks.push(Track::new(name));
+            Ok(self.tracks.last_mut().unwrap())
+        }
+    }
+
+    /// Get track by name.
+    ///
+    /// You should use [`get_track_mut`](AsyncRocketClient::get_track_mut) to create a track.
+    pub fn get_track(&self, name: &str) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.get_name() == name)
+    }
+
+    /// Get track by index, see
+    /// [`RocketClient::get_track_by_index`](crate::RocketClient::get_track_by_index).
+    pub fn get_track_by_index(&self, index: usize) -> Option<&Track> {
+        self.tracks.get(index)
+    }
+
+    /// Create a clone of the tracks in the session, see
+    /// [`RocketClient::save_tracks`](crate::RocketClient::save_tracks).
+    pub fn save_tracks(&self) -> Vec<Track> {
+        self.tracks.clone()
+    }
+
+    /// Send a SetRow message.
+    ///
+    /// This changes the current row on the tracker side.
+    ///
+    /// # Errors
+    ///
+    /// This method can return an [`Error::IOError`] if Rocket tracker disconnects.
+    pub async fn set_row(&mut self, row: u32) -> Result<(), Error> {
+        // Send SET_ROW message
+        let mut buf = Vec::new();
+        ClientCommand::SetRow(row).encode(&mut buf);
+        self.stream.write_all(&buf).await.map_err(Error::IOError)
+    }
+
+    /// Skip commands unknown to the client instead of failing, see
+    /// [`RocketClient::set_unknown_command_hook`](crate::RocketClient::set_unknown_command_hook).
+    pub fn set_unknown_command_hook<F: FnMut(u8) + Send + 'static>(&mut self, hook: F) {
+        self.unknown_command_hook = Some(UnknownCommandHook::new(hook));
+    }
+
+    /// Enable or disable key events, see
+    /// [`RocketClient::set_key_events`](crate::RocketClient::set_key_events).
+    pub fn set_key_events(&mut self, enabled: bool) {
+        self.key_events = enabled;
+    }
+
+    /// Wait for the next event from the tracker.
+    ///
+    /// Key changes from the tracker are applied to the tracks while waiting.
+    ///
+    /// This method is cancel safe, so it can be used in `tokio::select!`.
+    ///
+    /// # Errors
+    ///
+    /// This method can return an [`Error::IOError`] if Rocket tracker disconnects, or an
+    /// [`Error::Protocol`] if the tracker sends an invalid command.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_rocket::async_client::AsyncRocketClient;
+    /// # async fn f() {
+    /// # let mut rocket = AsyncRocketClient::new().await.unwrap();
+    /// loop {
+    ///     match rocket.next_event().await.unwrap() {
+    ///         // Do something with the various events.
+    ///         _ => (),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn next_event(&mut self) -> Result<Event, Error> {
+        loop {
+            while let Some(command) =
+                next_command(&mut self.decoder, &mut self.unknown_command_hook)
+                    .map_err(Error::Protocol)?
+            {
+                if let Some(event) = apply_command(&mut self.tracks, command, self.key_events)
+                    .map_err(Error::Protocol)?
+                {
+                    return Ok(event);
+                }
+            }
+
+            let mut buf = [0; 64];
+            match self.stream.read(&mut buf).await {
+                Ok(0) => return Err(Error::IOError(std::io::ErrorKind::UnexpectedEof.into())),
+                Ok(bytes_read) => self.decoder.feed(&buf[..bytes_read]),
+                Err(e) => return Err(Error::IOError(e)),
+            }
+        }
+    }
+
+    async fn handshake(&mut self) -> Result<(), Error> {
+        self.stream
+            .write_all(CLIENT_GREETING)
+            .await
+            .map_err(Error::Handshake)?;
+
+        let mut buf = [0; 12];
+        self.stream
+            .read_exact(&mut buf)
+            .await
+            .map_err(Error::Handshake)?;
+
+        if &buf == SERVER_GREETING {
+            Ok(())
+        } else {
+            Err(Error::HandshakeGreetingMismatch(buf))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_next_event() {
+        let (client, mut server) = tokio::io::duplex(256);
+        server.write_all(b"hello, demo!").await.unwrap();
+        let mut rocket = AsyncRocketClient::with_transport(client).await.unwrap();
+
+        let mut greeting = [0; 19];
+        server.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(&greeting, b"hello, synctracker!");
+
+        rocket.get_track_mut("test").await.unwrap();
+        let mut get_track = [0; 1 + 4 + 4];
+        server.read_exact(&mut get_track).await.unwrap();
+        assert_eq!(get_track, [2, 0, 0, 0, 4, b't', b'e', b's', b't']);
+
+        // SET_KEY track 0, row 5, value 1.0, linear
+        let mut buf = vec![0];
+        buf.extend_from_slice(&0u32.to_be_bytes());
+        buf.extend_from_slice(&5u32.to_be_bytes());
+        buf.extend_from_slice(&1f32.to_be_bytes());
+        buf.push(1);
+        // PAUSE
+        buf.extend_from_slice(&[4, 1]);
+        server.write_all(&buf).await.unwrap();
+
+        assert!(matches!(
+            rocket.next_event().await.unwrap(),
+            Event::Pause(true)
+        ));
+        assert_eq!(rocket.get_track("test").unwrap().get_value(5.), 1.0);
+
+        rocket.set_row(9).await.unwrap();
+        let mut set_row = [0; 5];
+        server.read_exact(&mut set_row).await.unwrap();
+        assert_eq!(set_row, [3, 0, 0, 0, 9]);
+
+        drop(server);
+        assert!(rocket.next_event().await.is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! This module bakes tracks into `f32` buffers, for uploading to the GPU.
+//!
+//! Instead of sampling hundreds of tracks with [`Track::get_value`] every frame, the tracks can
+//! be sampled once at a fixed rate and uploaded as a texture or storage buffer. Shaders then
+//! sample the buffer at the current row, using [`Baked::offset`] and [`Baked::stride`] to find
+//! each track's values.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rust_rocket::bake::{bake, Layout};
+//! # use rust_rocket::interpolation::Interpolation;
+//! # use rust_rocket::track::{Key, Track};
+//! let mut track = Track::new("fade");
+//! track.set_key(Key::new(0, 0., Interpolation::Linear));
+//! track.set_key(Key::new(4, 1., Interpolation::Step));
+//!
+//! // Two samples per row for rows 0 to 4
+//! let baked = bake(&[track], 0..4, 0.5, Layout::Planar);
+//! assert_eq!(baked.data(), [0., 0.125, 0.25, 0.375, 0.5, 0.625, 0.75, 0.875]);
+//! assert_eq!(baked.offset("fade"), Some(0));
+//! ```
+use crate::track::Track;
+
+use std::ops::Range;
+
+/// How the samples of several tracks are arranged in a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// The samples of all tracks for one row come after each other, `[a0, b0, a1, b1, ...]`.
+    Interleaved,
+    /// All samples of one track come after each other, `[a0, a1, ..., b0, b1, ...]`.
+    Planar,
+}
+
+/// Tracks baked with [`bake`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Baked {
+    data: Vec<f32>,
+    names: Vec<String>,
+    layout: Layout,
+    sample_count: usize,
+    first_row: f32,
+    rows_per_sample: f32,
+}
+
+impl Baked {
+    /// Get the samples.
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Take the samples.
+    pub fn into_data(self) -> Vec<f32> {
+        self.data
+    }
+
+    /// Get the layout of the samples.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Get the number of samples per track.
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    /// Get the names of the baked tracks, in the order they are stored in.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Get the row of the first sample.
+    pub fn first_row(&self) -> f32 {
+        self.first_row
+    }
+
+    /// Get the number of rows between samples.
+    pub fn rows_per_sample(&self) -> f32 {
+        self.rows_per_sample
+    }
+
+    /// Get the distance between consecutive samples of a track in [`data`](Baked::data).
+    pub fn stride(&self) -> usize {
+        match self.layout {
+            Layout::Interleaved => self.names.len(),
+            Layout::Planar => 1,
+        }
+    }
+
+    /// Get the position of the first sample of a track in [`data`](Baked::data).
+    pub fn offset(&self, name: &str) -> Option<usize> {
+        let index = self.names.iter().position(|n| n == name)?;
+        Some(self.offset_of(index))
+    }
+
+    /// Get the names of all tracks with the positions of their first samples.
+    pub fn offsets(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        self.names
+            .iter()
+            .enumerate()
+            .map(move |(index, name)| (name.as_str(), self.offset_of(index)))
+    }
+
+    /// Get sample number `sample` of a track.
+    pub fn get(&self, name: &str, sample: usize) -> Option<f32> {
+        if sample >= self.sample_count {
+            return None;
+        }
+        let offset = self.offset(name)?;
+        Some(self.data[offset + sample * self.stride()])
+    }
+
+    fn offset_of(&self, index: usize) -> usize {
+        match self.layout {
+            Layout::Interleaved => index,
+            Layout::Planar => index * self.sample_count,
+        }
+    }
+}
+
+/// Sample `tracks` every `rows_per_sample` rows in `rows`.
+///
+/// The first sample is at `rows.start`, and samples are taken while the row is before
+/// `rows.end`.
+///
+/// # Panics
+///
+/// If `rows_per_sample` is not positive and finite.
+pub fn bake(tracks: &[Track], rows: Range<u32>, rows_per_sample: f32, layout: Layout) -> Baked {
+    assert!(
+        rows_per_sample.is_finite() && rows_per_sample > 0.,
+        "rows_per_sample must be positive"
+    );
+    let len = rows.end.saturating_sub(rows.start);
+    let sample_count = (f64::from(len) / f64::from(rows_per_sample)).ceil() as usize;
+    let first_row = rows.start as f32;
+
+    let mut baked = Baked {
+        data: vec![0.; sample_count * tracks.len()],
+        names: tracks.iter().map(|t| t.get_name().to_owned()).collect(),
+        layout,
+        sample_count,
+        first_row,
+        rows_per_sample,
+    };
+
+    let stride = baked.stride();
+    for (index, track) in tracks.iter().enumerate() {
+        let offset = baked.offset_of(index);
+        let mut cursor = track.cursor();
+        for sample in 0..sample_count {
+            let row = first_row + sample as f32 * rows_per_sample;
+            baked.data[offset + sample * stride] = cursor.get_value(row);
+        }
+    }
+
+    baked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::track::Key;
+
+    #[test]
+    fn test_layouts() {
+        let tracks: Vec<Track> = (0..3)
+            .map(|i| {
+                let mut track = Track::new(format!("{}", i));
+                track.set_key(Key::new(0, i as f32, Interpolation::Linear));
+                track.set_key(Key::new(10, i as f32 + 10., Interpolation::Step));
+                track
+            })
+            .collect();
+
+        let interleaved = bake(&tracks, 2..7, 2., Layout::Interleaved);
+        assert_eq!(interleaved.sample_count(), 3);
+        assert_eq!(interleaved.data(), [2., 3., 4., 4., 5., 6., 6., 7., 8.]);
+
+        let planar = bake(&tracks, 2..7, 2., Layout::Planar);
+        assert_eq!(planar.data(), [2., 4., 6., 3., 5., 7., 4., 6., 8.]);
+        assert_eq!(
+            planar.offsets().collect::<Vec<_>>(),
+            [("0", 0), ("1", 3), ("2", 6)]
+        );
+        for (name, _) in planar.offsets() {
+            for sample in 0..3 {
+                assert_eq!(planar.get(name, sample), interleaved.get(name, sample));
+            }
+        }
+        assert_eq!(planar.get("2", 3), None);
+    }
+}
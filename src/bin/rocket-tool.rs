@@ -0,0 +1,210 @@
+//! A tool for inspecting, converting and diffing track files.
+//!
+//! Build with `cargo install rust-rocket --features tool`, and run `rocket-tool help`.
+use rust_rocket::formats::{bundle, librocket, rocket_xml};
+use rust_rocket::track::{Key, Track};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "\
+Usage: rocket-tool <command> <arguments>
+
+Commands:
+    list <tracks>               List tracks and their key counts
+    dump <tracks> [track]       Print the keys of all tracks or of one track
+    convert <input> <output>    Convert tracks to another format
+    diff <a> <b>                Compare tracks by name and row, exits with 1 if they differ
+    help                        Print this message
+
+Errors exit with 2.
+
+Tracks can be prefixed with a format, for example `xml:demo.rocket`:
+    bundle:     rust-rocket bundle, see rust_rocket::formats::bundle
+    bincode:    A Vec<Track> serialized with bincode, as saved by older versions of the examples
+    xml:        A GNU Rocket editor .rocket project
+    librocket:  librocket's <base>_<track>.track files, for example librocket:data/sync
+
+Without a prefix, files ending with .rocket are read as XML. Other inputs are read as bundles
+if they start with the bundle magic, and as bincode otherwise. Other outputs are written as
+bundles.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Bundle,
+    Bincode,
+    Xml,
+    Librocket,
+}
+
+/// Split a track file argument into its format and path.
+fn parse_spec(spec: &str) -> (Option<Format>, &str) {
+    let formats = [
+        ("bundle:", Format::Bundle),
+        ("bincode:", Format::Bincode),
+        ("xml:", Format::Xml),
+        ("librocket:", Format::Librocket),
+    ];
+    for (prefix, format) in formats {
+        if let Some(path) = spec.strip_prefix(prefix) {
+            return (Some(format), path);
+        }
+    }
+    let is_xml = Path::new(spec).extension().is_some_and(|e| e == "rocket");
+    (is_xml.then_some(Format::Xml), spec)
+}
+
+fn read(spec: &str) -> Result<Vec<Track>, Box<dyn Error>> {
+    let (format, path) = parse_spec(spec);
+    if format == Some(Format::Librocket) {
+        return Ok(librocket::read_tracks(path)?);
+    }
+
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    let format = format.unwrap_or(if bytes.starts_with(bundle::MAGIC) {
+        Format::Bundle
+    } else {
+        Format::Bincode
+    });
+
+    Ok(match format {
+        Format::Bundle => bundle::from_bytes(&bytes)?,
+        Format::Bincode => bincode::deserialize(&bytes)?,
+        Format::Xml => rocket_xml::read(&bytes[..])?,
+        Format::Librocket => unreachable!(),
+    })
+}
+
+fn write(spec: &str, tracks: &[Track]) -> Result<(), Box<dyn Error>> {
+    let (format, path) = parse_spec(spec);
+    if format == Some(Format::Librocket) {
+        return Ok(librocket::write_tracks(path, tracks)?);
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+    match format.unwrap_or(Format::Bundle) {
+        Format::Bundle => bundle::write(&mut file, tracks)?,
+        Format::Bincode => bincode::serialize_into(&mut file, tracks)?,
+        Format::Xml => rocket_xml::write(&mut file, tracks)?,
+        Format::Librocket => unreachable!(),
+    }
+    file.flush()?;
+    Ok(())
+}
+
+fn format_key(key: &Key) -> String {
+    format!(
+        "{:>8} {:>14} {:?}",
+        key.get_row(),
+        key.get_value(),
+        key.get_interpolation()
+    )
+}
+
+fn list(tracks: &[Track]) {
+    for track in tracks {
+        println!("{} ({} keys)", track.get_name(), track.keys().len());
+    }
+}
+
+fn dump(tracks: &[Track], name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut found = false;
+    for track in tracks
+        .iter()
+        .filter(|t| name.is_none_or(|n| t.get_name() == n))
+    {
+        found = true;
+        println!("{}", track.get_name());
+        for key in track.keys() {
+            println!("{}", format_key(key));
+        }
+    }
+    match name {
+        Some(name) if !found => Err(format!("Track {:?} does not exist", name).into()),
+        _ => Ok(()),
+    }
+}
+
+/// Print the differences between two sets of tracks. Returns true if there are any.
+fn diff(a: &[Track], b: &[Track]) -> bool {
+    let find = |tracks: &'_ [Track], name: &str| -> Option<usize> {
+        tracks.iter().position(|t| t.get_name() == name)
+    };
+    let names: BTreeSet<&str> = a.iter().chain(b).map(|t| t.get_name()).collect();
+
+    let mut differ = false;
+    for name in names {
+        let (a, b) = match (find(a, name), find(b, name)) {
+            (Some(i), Some(j)) => (&a[i], &b[j]),
+            (Some(_), None) => {
+                println!("- {}", name);
+                differ = true;
+                continue;
+            }
+            (None, _) => {
+                println!("+ {}", name);
+                differ = true;
+                continue;
+            }
+        };
+
+        let rows: BTreeSet<u32> = a.keys().iter().chain(b.keys()).map(Key::get_row).collect();
+        let mut header = false;
+        for row in rows {
+            let (a, b) = (a.key_at(row), b.key_at(row));
+            if a == b {
+                continue;
+            }
+            if !header {
+                println!("  {}", name);
+                header = true;
+            }
+            if let Some(key) = a {
+                println!("-   {}", format_key(key));
+            }
+            if let Some(key) = b {
+                println!("+   {}", format_key(key));
+            }
+        }
+        differ |= header;
+    }
+    differ
+}
+
+fn run(args: &[String]) -> Result<bool, Box<dyn Error>> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["list", tracks] => list(&read(tracks)?),
+        ["dump", tracks] => dump(&read(tracks)?, None)?,
+        ["dump", tracks, name] => dump(&read(tracks)?, Some(name))?,
+        ["convert", input, output] => write(output, &read(input)?)?,
+        ["diff", a, b] => return Ok(diff(&read(a)?, &read(b)?)),
+        ["help"] | ["--help"] | ["-h"] => print!("{}", USAGE),
+        _ => {
+            eprint!("{}", USAGE);
+            exit(2);
+        }
+    }
+    Ok(false)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(differ) => exit(differ as i32),
+        Err(e) => {
+            eprintln!("rocket-tool: {}", e);
+            let mut source = e.source();
+            while let Some(e) = source {
+                eprintln!("  caused by: {}", e);
+                source = e.source();
+            }
+            exit(2);
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! This module implements the `sync_*` C API of the reference C librocket, so that C and C++
+//! demos can link this crate instead of librocket without changing their code.
+//!
+//! The header is `include/sync.h`. Build a static or dynamic library with
+//!
+//! ```text
+//! cargo rustc --release --features capi --crate-type staticlib
+//! cargo rustc --release --features capi --crate-type cdylib
+//! ```
+//!
+//! When a device is created, it loads the track files of its base path, see
+//! [`formats::librocket`](crate::formats::librocket), and plays them with a [`RocketPlayer`].
+//! After [`sync_tcp_connect`], tracks come from a [`RocketClient`] instead. If the connection
+//! is lost, the tracks received so far are kept until the next connection.
+//!
+//! Unlike in librocket, `struct sync_track` is opaque, so its `name`, `keys` and `num_keys`
+//! fields can't be read, and `sync_set_io_cb` is not supported. The same library works with
+//! and without `SYNC_PLAYER`.
+use crate::client::{self, Event, RocketClient};
+use crate::formats::librocket;
+use crate::player::RocketPlayer;
+use crate::track::Track;
+
+use std::ffi::{c_void, CStr};
+use std::os::raw::{c_char, c_int, c_ushort};
+use std::path::PathBuf;
+use std::ptr;
+
+/// Callbacks for [`sync_update`], `struct sync_cb`.
+#[repr(C)]
+pub struct SyncCb {
+    /// Pause playback if the second argument is not 0, resume otherwise.
+    pub pause: Option<unsafe extern "C" fn(*mut c_void, c_int)>,
+    /// Seek to a row.
+    pub set_row: Option<unsafe extern "C" fn(*mut c_void, c_int)>,
+    /// Return 1 if the demo is playing, 0 otherwise.
+    pub is_playing: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+}
+
+/// A sync device, `struct sync_device`.
+pub struct SyncDevice {
+    base: PathBuf,
+    player: RocketPlayer,
+    client: Option<RocketClient>,
+    /// Boxed, so that the pointers returned by `sync_get_track` stay valid when this grows.
+    #[allow(clippy::vec_box)]
+    tracks: Vec<Box<SyncTrack>>,
+}
+
+/// A track returned by [`sync_get_track`], `struct sync_track`.
+pub struct SyncTrack {
+    device: *const SyncDevice,
+    name: String,
+    /// Position in the device's tracks, which is also the position in the client's tracks.
+    index: usize,
+}
+
+impl SyncDevice {
+    fn new(base: PathBuf) -> Self {
+        let player =
+            librocket::load_player(&base).unwrap_or_else(|_| RocketPlayer::new(Vec::new()));
+        Self {
+            base,
+            player,
+            client: None,
+            tracks: Vec::new(),
+        }
+    }
+
+    fn connect(&mut self, host: &str, port: u16) -> Result<(), client::Error> {
+        let mut client = RocketClient::connect(host, port)?;
+        // Request the tracks in the same order, so that the indices match
+        for track in &self.tracks {
+            client.get_track_mut(&track.name)?;
+        }
+        self.client = Some(client);
+        Ok(())
+    }
+
+    /// Drop the client, keeping its tracks for playback.
+    fn disconnect(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.player = RocketPlayer::new(client.save_tracks());
+        }
+    }
+
+    fn get_track(&self, track: &SyncTrack) -> Option<&Track> {
+        match &self.client {
+            Some(client) => client.get_track_by_index(track.index),
+            None => self.player.get_track(&track.name),
+        }
+    }
+
+    fn save_tracks(&self) -> Result<(), librocket::Error> {
+        let tracks: Vec<Track> = self
+            .tracks
+            .iter()
+            .filter_map(|track| self.get_track(track).cloned())
+            .collect();
+        librocket::write_tracks(&self.base, &tracks)
+    }
+}
+
+/// Create a device. Track files named `<base>_<track name>.track` are loaded for playback.
+///
+/// Returns null if `base` is not UTF-8.
+///
+/// # Safety
+///
+/// `base` must be a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn sync_create_device(base: *const c_char) -> *mut SyncDevice {
+    match CStr::from_ptr(base).to_str() {
+        Ok(base) => Box::into_raw(Box::new(SyncDevice::new(PathBuf::from(base)))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Destroy a device and its tracks.
+///
+/// # Safety
+///
+/// `d` must be null or a device from [`sync_create_device`] which is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sync_destroy_device(d: *mut SyncDevice) {
+    if !d.is_null() {
+        drop(Box::from_raw(d));
+    }
+}
+
+/// Connect to a tracker and request all tracks of the device.
+///
+/// Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `d` must be a device from [`sync_create_device`] and `host` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn sync_tcp_connect(
+    d: *mut SyncDevice,
+    host: *const c_char,
+    port: c_ushort,
+) -> c_int {
+    let device = &mut *d;
+    let connected = CStr::from_ptr(host)
+        .to_str()
+        .ok()
+        .and_then(|host| device.connect(host, port).ok());
+    match connected {
+        Some(()) => 0,
+        None => -1,
+    }
+}
+
+/// Process the commands received from the tracker, and send `row` to the tracker while the
+/// demo is playing.
+///
+/// Returns 0 on success, and -1 if the device is not connected or the connection was lost.
+/// Reconnect with [`sync_tcp_connect`].
+///
+/// # Safety
+///
+/// `d` must be a device from [`sync_create_device`], and `cb` null or valid callbacks for
+/// `cb_param`.
+#[no_mangle]
+pub unsafe extern "C" fn sync_update(
+    d: *mut SyncDevice,
+    row: c_int,
+    cb: *const SyncCb,
+    cb_param: *mut c_void,
+) -> c_int {
+    let device = &mut *d;
+    let cb = cb.as_ref();
+    loop {
+        let event = match &mut device.client {
+            Some(client) => client.poll_events(),
+            None => return -1,
+        };
+        match event {
+            Ok(Some(Event::SetRow(row))) => {
+                if let Some(set_row) = cb.and_then(|cb| cb.set_row) {
+                    set_row(cb_param, row as c_int);
+                }
+            }
+            Ok(Some(Event::Pause(paused))) => {
+                if let Some(pause) = cb.and_then(|cb| cb.pause) {
+                    pause(cb_param, paused as c_int);
+                }
+            }
+            Ok(Some(Event::SaveTracks)) => {
+                let _ = device.save_tracks();
+            }
+            Ok(Some(_)) => (),
+            Ok(None) => break,
+            Err(_) => {
+                device.disconnect();
+                return -1;
+            }
+        }
+    }
+
+    let playing = match cb.and_then(|cb| cb.is_playing) {
+        Some(is_playing) => is_playing(cb_param) != 0,
+        None => false,
+    };
+    if playing && row >= 0 {
+        if let Some(client) = &mut device.client {
+            if client.get_row() != row as u32 && client.set_row(row as u32).is_err() {
+                device.disconnect();
+                return -1;
+            }
+        }
+    }
+    0
+}
+
+/// Save all tracks of the device to track files, which [`sync_create_device`] loads.
+///
+/// Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `d` must be a device from [`sync_create_device`].
+#[no_mangle]
+pub unsafe extern "C" fn sync_save_tracks(d: *const SyncDevice) -> c_int {
+    match (*d).save_tracks() {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Get a track by name, requesting it from the tracker if the device is connected.
+///
+/// The track lives as long as the device. Returns null if `name` is not UTF-8.
+///
+/// # Safety
+///
+/// `d` must be a device from [`sync_create_device`] and `name` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn sync_get_track(
+    d: *mut SyncDevice,
+    name: *const c_char,
+) -> *const SyncTrack {
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(name) => name,
+        Err(_) => return ptr::null(),
+    };
+    let device = &mut *d;
+    if let Some(track) = device.tracks.iter().find(|t| t.name == name) {
+        return &**track;
+    }
+
+    if let Some(client) = &mut device.client {
+        if client.get_track_mut(name).is_err() {
+            device.disconnect();
+        }
+    }
+    let index = device.tracks.len();
+    device.tracks.push(Box::new(SyncTrack {
+        device: d,
+        name: name.to_owned(),
+        index,
+    }));
+    &*device.tracks[index]
+}
+
+/// Get the value of a track at a row. Tracks without keys are 0.
+///
+/// # Safety
+///
+/// `t` must be a track from [`sync_get_track`] whose device has not been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn sync_get_val(t: *const SyncTrack, row: f64) -> f64 {
+    let track = &*t;
+    (*track.device)
+        .get_track(track)
+        .map_or(0.0, |track| f64::from(track.get_value(row as f32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::mock::MockTracker;
+    use crate::track::Key;
+    use std::ffi::CString;
+    use std::net::TcpListener;
+
+    unsafe extern "C" fn set_row(param: *mut c_void, row: c_int) {
+        *(param as *mut c_int) = row;
+    }
+
+    #[test]
+    fn test_device() {
+        let dir = std::env::temp_dir().join(format!("rust-rocket-capi-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("sync");
+        let mut track = Track::new("a");
+        track.set_key(Key::new(0, 1.0, Interpolation::Step));
+        librocket::write_tracks(&base, &[track]).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accept = std::thread::spawn(move || MockTracker::accept(&listener).unwrap());
+
+        let cb = SyncCb {
+            pause: None,
+            set_row: Some(set_row),
+            is_playing: None,
+        };
+        let mut row: c_int = -1;
+        let row = &mut row as *mut c_int;
+        let param = row as *mut c_void;
+        unsafe {
+            let base = CString::new(base.to_str().unwrap()).unwrap();
+            let [name_a, name_b] = ["a", "b"].map(|name| CString::new(name).unwrap());
+            let d = sync_create_device(base.as_ptr());
+            let a = sync_get_track(d, name_a.as_ptr());
+            let b = sync_get_track(d, name_b.as_ptr());
+            assert_eq!(sync_get_track(d, name_a.as_ptr()), a);
+            assert_eq!(sync_get_val(a, 3.), 1.);
+            assert_eq!(sync_get_val(b, 3.), 0.);
+            assert_eq!(sync_update(d, 0, &cb, param), -1);
+
+            let host = CString::new("127.0.0.1").unwrap();
+            assert_eq!(sync_tcp_connect(d, host.as_ptr(), port), 0);
+            let mut tracker = accept.join().unwrap();
+            assert_eq!(tracker.wait_for_track("b").unwrap(), 1);
+            tracker
+                .set_key(1, Key::new(0, 2.0, Interpolation::Step))
+                .unwrap();
+            tracker.set_row(5).unwrap();
+            loop {
+                assert_eq!(sync_update(d, 0, &cb, param), 0);
+                if *row == 5 {
+                    break;
+                }
+            }
+            assert_eq!(sync_get_val(a, 3.), 0.);
+            assert_eq!(sync_get_val(b, 3.), 2.);
+
+            drop(tracker);
+            while sync_update(d, 0, &cb, param) == 0 {}
+            assert_eq!(sync_get_val(b, 3.), 2.);
+            assert_eq!(sync_save_tracks(d), 0);
+            sync_destroy_device(d);
+        }
+
+        let player = librocket::load_player(&base).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(player.get_track("b").unwrap().get_value(0.), 2.);
+    }
+}
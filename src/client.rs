@@ -1,13 +1,16 @@
 //! This module contains the main client code, including the [`RocketClient`] type.
-use crate::interpolation::*;
+use crate::protocol::{ClientCommand, Command, Decoder, ServerCommand};
+use crate::protocol::{CLIENT_GREETING, SERVER_GREETING};
+use crate::timing::Timing;
 use crate::track::*;
+use crate::transport::Transport;
 
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::io::prelude::*;
-use std::io::Cursor;
+use std::io::ErrorKind;
 use std::net::TcpStream;
 use thiserror::Error;
 
+pub use crate::protocol::ProtocolError;
+
 #[derive(Debug, Error)]
 /// The `Error` Type. This is the main error type.
 pub enum Error {
@@ -21,19 +24,35 @@ pub enum Error {
     #[error("The Rocket server greeting {0:?} wasn't correct")]
     /// Handshake was performed but the the received greeting wasn't correct
     HandshakeGreetingMismatch([u8; 12]),
-    #[error("Cannot set Rocket's TCP connection to nonblocking mode")]
-    /// Error from [`TcpStream::set_nonblocking`]
+    #[error("Cannot set Rocket's connection to nonblocking mode")]
+    /// Error from [`Transport::set_nonblocking`]
     SetNonblocking(#[source] std::io::Error),
     #[error("Rocket server disconnected")]
     /// Network IO error during operation
     IOError(#[source] std::io::Error),
+    #[error("Rocket server sent an invalid command")]
+    /// The tracker sent a command which could not be handled
+    Protocol(#[source] ProtocolError),
 }
 
-#[derive(Debug)]
-enum ClientState {
-    New,
-    Incomplete(usize),
-    Complete,
+/// A callback for commands unknown to the client, see
+/// [`RocketClient::set_unknown_command_hook`].
+pub(crate) struct UnknownCommandHook(Box<dyn FnMut(u8) + Send>);
+
+impl UnknownCommandHook {
+    pub(crate) fn new<F: FnMut(u8) + Send + 'static>(hook: F) -> Self {
+        Self(Box::new(hook))
+    }
+
+    pub(crate) fn call(&mut self, cmd: u8) {
+        (self.0)(cmd)
+    }
+}
+
+impl std::fmt::Debug for UnknownCommandHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("UnknownCommandHook")
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -46,24 +65,42 @@ pub enum Event {
     /// The tracker asks us to save our track data.
     /// You may want to call [`RocketClient::save_tracks`] after receiving this event.
     SaveTracks,
-}
-
-enum ReceiveResult {
-    Some(Event),
-    None,
-    Incomplete,
+    /// A key was inserted or updated by the tracker.
+    /// Only reported after enabling key events with
+    /// [`set_key_events`](RocketClient::set_key_events).
+    KeyChanged {
+        /// Index of the track, see [`get_track_by_index`](RocketClient::get_track_by_index).
+        track: usize,
+        /// Row of the key.
+        row: u32,
+    },
+    /// A key was deleted by the tracker.
+    /// Only reported after enabling key events with
+    /// [`set_key_events`](RocketClient::set_key_events).
+    KeyDeleted {
+        /// Index of the track, see [`get_track_by_index`](RocketClient::get_track_by_index).
+        track: usize,
+        /// Row of the deleted key.
+        row: u32,
+    },
 }
 
 #[derive(Debug)]
 /// The `RocketClient` type. This contains the connected socket and other fields.
-pub struct RocketClient {
-    stream: TcpStream,
-    state: ClientState,
-    cmd: Vec<u8>,
+///
+/// The client is generic over its [`Transport`], which is a [`TcpStream`] by default.
+pub struct RocketClient<S: Transport = TcpStream> {
+    stream: S,
+    decoder: Decoder<ServerCommand>,
     tracks: Vec<Track>,
+    unknown_command_hook: Option<UnknownCommandHook>,
+    key_events: bool,
+    row: u32,
+    paused: bool,
+    pub(crate) timing: Timing,
 }
 
-impl RocketClient {
+impl RocketClient<TcpStream> {
     /// Construct a new RocketClient.
     ///
     /// This constructs a new Rocket client and connects to localhost on port 1338.
@@ -100,12 +137,41 @@ impl RocketClient {
     /// ```
     pub fn connect(host: &str, port: u16) -> Result<Self, Error> {
         let stream = TcpStream::connect((host, port)).map_err(Error::Connect)?;
+        Self::with_transport(stream)
+    }
+}
 
+impl<S: Transport> RocketClient<S> {
+    /// Construct a new RocketClient over an already connected [`Transport`].
+    ///
+    /// This performs the handshake over `stream` and switches it to nonblocking mode.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Handshake`] if the handshake fails, or [`Error::SetNonblocking`] if the
+    /// transport cannot be made nonblocking.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(unix)] {
+    /// # use rust_rocket::RocketClient;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// let stream = UnixStream::connect("/tmp/rocket.sock").unwrap();
+    /// let mut rocket = RocketClient::with_transport(stream).unwrap();
+    /// # }
+    /// ```
+    pub fn with_transport(stream: S) -> Result<Self, Error> {
         let mut rocket = Self {
             stream,
-            state: ClientState::New,
-            cmd: Vec::new(),
+            decoder: Decoder::new(),
             tracks: Vec::new(),
+            unknown_command_hook: None,
+            key_events: false,
+            row: 0,
+            paused: true,
+            timing: Timing::default(),
         };
 
         rocket.handshake()?;
@@ -144,9 +210,8 @@ impl RocketClient {
             Ok(&mut self.tracks[i])
         } else {
             // Send GET_TRACK message
-            let mut buf = vec![2];
-            buf.write_u32::<BigEndian>(name.len() as u32).unwrap();
-            buf.extend_from_slice(&name.as_bytes());
+            let mut buf = Vec::new();
+            ClientCommand::GetTrack(name.to_owned()).encode(&mut buf);
             self.stream.write_all(&buf).map_err(Error::IOError)?;
 
             self.tracks.push(Track::new(name));
@@ -161,6 +226,14 @@ impl RocketClient {
         self.tracks.iter().find(|t| t.get_name() == name)
     }
 
+    /// Get track by index.
+    ///
+    /// Tracks are indexed in the order they were created with
+    /// [`get_track_mut`](RocketClient::get_track_mut).
+    pub fn get_track_by_index(&self, index: usize) -> Option<&Track> {
+        self.tracks.get(index)
+    }
+
     /// Create a clone of the tracks in the session which can then be serialized to a file in any
     /// format with a serde implementation.
     /// Tracks can be turned into a [`RocketPlayer`](crate::RocketPlayer::new) for playback.
@@ -176,12 +249,68 @@ impl RocketClient {
     ///
     /// This method can return an [`Error::IOError`] if Rocket tracker disconnects.
     pub fn set_row(&mut self, row: u32) -> Result<(), Error> {
+        self.row = row;
+
         // Send SET_ROW message
-        let mut buf = vec![3];
-        buf.write_u32::<BigEndian>(row).unwrap();
+        let mut buf = Vec::new();
+        ClientCommand::SetRow(row).encode(&mut buf);
         self.stream.write_all(&buf).map_err(Error::IOError)
     }
 
+    /// Get the current row.
+    ///
+    /// This is the row last set by the tracker with [`Event::SetRow`] or by
+    /// [`set_row`](RocketClient::set_row).
+    pub fn get_row(&self) -> u32 {
+        self.row
+    }
+
+    /// Check if the tracker is paused. The tracker is paused until it reports otherwise
+    /// with [`Event::Pause`].
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Skip commands unknown to the client instead of failing, and pass them to `hook`.
+    ///
+    /// By default, [`poll_events`](RocketClient::poll_events) returns
+    /// [`ProtocolError::UnknownCommand`] for unknown commands. With a hook, unknown commands are
+    /// assumed to have no payload, which is the best guess for extensions of the protocol.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_rocket::RocketClient;
+    /// # let mut rocket = RocketClient::new().unwrap();
+    /// rocket.set_unknown_command_hook(|cmd| eprintln!("Unknown Rocket command {}", cmd));
+    /// ```
+    pub fn set_unknown_command_hook<F: FnMut(u8) + Send + 'static>(&mut self, hook: F) {
+        self.unknown_command_hook = Some(UnknownCommandHook::new(hook));
+    }
+
+    /// Enable or disable [`Event::KeyChanged`] and [`Event::KeyDeleted`]. They are disabled by
+    /// default.
+    ///
+    /// Key events let you invalidate data derived from a track only when it has changed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_rocket::RocketClient;
+    /// # use rust_rocket::client::Event;
+    /// # let mut rocket = RocketClient::new().unwrap();
+    /// rocket.set_key_events(true);
+    /// while let Some(event) = rocket.poll_events().unwrap() {
+    ///     if let Event::KeyChanged { track, .. } | Event::KeyDeleted { track, .. } = event {
+    ///         let track = rocket.get_track_by_index(track).unwrap();
+    ///         println!("{} changed", track.get_name());
+    ///     }
+    /// }
+    /// ```
+    pub fn set_key_events(&mut self, enabled: bool) {
+        self.key_events = enabled;
+    }
+
     /// Poll for new events from the tracker.
     ///
     /// This polls from events from the tracker.
@@ -190,7 +319,8 @@ impl RocketClient {
     ///
     /// # Errors
     ///
-    /// This method can return an [`Error::IOError`] if Rocket tracker disconnects.
+    /// This method can return an [`Error::IOError`] if Rocket tracker disconnects, or an
+    /// [`Error::Protocol`] if the tracker sends an invalid command.
     ///
     /// # Examples
     ///
@@ -206,117 +336,193 @@ impl RocketClient {
     /// ```
     pub fn poll_events(&mut self) -> Result<Option<Event>, Error> {
         loop {
-            let result = self.poll_event()?;
-            match result {
-                ReceiveResult::None => return Ok(None),
-                ReceiveResult::Incomplete => (),
-                ReceiveResult::Some(event) => return Ok(Some(event)),
-            }
-        }
-    }
-
-    fn poll_event(&mut self) -> Result<ReceiveResult, Error> {
-        match self.state {
-            ClientState::New => {
-                let mut buf = [0; 1];
-                match self.stream.read_exact(&mut buf) {
-                    Ok(()) => {
-                        self.cmd.extend_from_slice(&buf);
-                        match self.cmd[0] {
-                            0 => self.state = ClientState::Incomplete(4 + 4 + 4 + 1), //SET_KEY
-                            1 => self.state = ClientState::Incomplete(4 + 4),         //DELETE_KEY
-                            3 => self.state = ClientState::Incomplete(4),             //SET_ROW
-                            4 => self.state = ClientState::Incomplete(1),             //PAUSE
-                            5 => self.state = ClientState::Complete,                  //SAVE_TRACKS
-                            _ => self.state = ClientState::Complete, // Error / Unknown
-                        }
-                        Ok(ReceiveResult::Incomplete)
-                    }
-                    Err(e) => match e.kind() {
-                        std::io::ErrorKind::WouldBlock => Ok(ReceiveResult::None),
-                        _ => Err(Error::IOError(e)),
-                    },
-                }
-            }
-            ClientState::Incomplete(bytes) => {
-                let mut buf = vec![0; bytes];
-                match self.stream.read(&mut buf) {
-                    Ok(bytes_read) => {
-                        self.cmd.extend_from_slice(&buf);
-                        if bytes - bytes_read > 0 {
-                            self.state = ClientState::Incomplete(bytes - bytes_read);
-                        } else {
-                            self.state = ClientState::Complete;
+            match next_command(&mut self.decoder, &mut self.unknown_command_hook)
+                .map_err(Error::Protocol)?
+            {
+                Some(command) => {
+                    if let Some(event) = apply_command(&mut self.tracks, command, self.key_events)
+                        .map_err(Error::Protocol)?
+                    {
+                        match event {
+                            Event::SetRow(row) => self.row = row,
+                            Event::Pause(paused) => self.paused = paused,
+                            _ => (),
                         }
-                        Ok(ReceiveResult::Incomplete)
+                        return Ok(Some(event));
                     }
-                    Err(e) => match e.kind() {
-                        std::io::ErrorKind::WouldBlock => Ok(ReceiveResult::None),
-                        _ => Err(Error::IOError(e)),
-                    },
                 }
-            }
-            ClientState::Complete => {
-                let mut result = ReceiveResult::None;
-                {
-                    let mut cursor = Cursor::new(&self.cmd);
-                    let cmd = cursor.read_u8().unwrap();
-                    match cmd {
-                        0 => {
-                            let track =
-                                &mut self.tracks[cursor.read_u32::<BigEndian>().unwrap() as usize];
-                            let row = cursor.read_u32::<BigEndian>().unwrap();
-                            let value = cursor.read_f32::<BigEndian>().unwrap();
-                            let interpolation = Interpolation::from(cursor.read_u8().unwrap());
-                            let key = Key::new(row, value, interpolation);
-
-                            track.set_key(key);
-                        }
-                        1 => {
-                            let track =
-                                &mut self.tracks[cursor.read_u32::<BigEndian>().unwrap() as usize];
-                            let row = cursor.read_u32::<BigEndian>().unwrap();
-
-                            track.delete_key(row);
-                        }
-                        3 => {
-                            let row = cursor.read_u32::<BigEndian>().unwrap();
-                            result = ReceiveResult::Some(Event::SetRow(row));
-                        }
-                        4 => {
-                            let flag = cursor.read_u8().unwrap() == 1;
-                            result = ReceiveResult::Some(Event::Pause(flag));
-                        }
-                        5 => {
-                            result = ReceiveResult::Some(Event::SaveTracks);
-                        }
-                        _ => println!("Unknown {:?}", cmd),
+                None => {
+                    let mut buf = [0; 256];
+                    match self.stream.read(&mut buf) {
+                        Ok(0) => return Err(Error::IOError(ErrorKind::UnexpectedEof.into())),
+                        Ok(bytes_read) => self.decoder.feed(&buf[..bytes_read]),
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                        Err(e) => return Err(Error::IOError(e)),
                     }
                 }
-
-                self.cmd.clear();
-                self.state = ClientState::New;
-
-                Ok(result)
             }
         }
     }
 
     fn handshake(&mut self) -> Result<(), Error> {
-        let client_greeting = b"hello, synctracker!";
-        let server_greeting = b"hello, demo!";
-
         self.stream
-            .write_all(client_greeting)
+            .write_all(CLIENT_GREETING)
             .map_err(Error::Handshake)?;
 
         let mut buf = [0; 12];
         self.stream.read_exact(&mut buf).map_err(Error::Handshake)?;
 
-        if &buf == server_greeting {
+        if &buf == SERVER_GREETING {
             Ok(())
         } else {
             Err(Error::HandshakeGreetingMismatch(buf))
         }
     }
 }
+
+/// Decode the next command, passing unknown commands to `hook` if there is one.
+pub(crate) fn next_command(
+    decoder: &mut Decoder<ServerCommand>,
+    hook: &mut Option<UnknownCommandHook>,
+) -> Result<Option<ServerCommand>, ProtocolError> {
+    loop {
+        match (decoder.decode(), &mut *hook) {
+            (Err(ProtocolError::UnknownCommand(cmd)), Some(hook)) => hook.call(cmd),
+            (result, _) => return result,
+        }
+    }
+}
+
+/// Apply a command received from the tracker to `tracks`.
+///
+/// Key changes are reported as events if `key_events` is set.
+pub(crate) fn apply_command(
+    tracks: &mut [Track],
+    command: ServerCommand,
+    key_events: bool,
+) -> Result<Option<Event>, ProtocolError> {
+    match command {
+        ServerCommand::SetKey { track, key } => {
+            tracks
+                .get_mut(track as usize)
+                .ok_or(ProtocolError::BadTrackIndex(track))?
+                .set_key(key);
+            Ok(key_events.then_some(Event::KeyChanged {
+                track: track as usize,
+                row: key.row,
+            }))
+        }
+        ServerCommand::DeleteKey { track, row } => {
+            tracks
+                .get_mut(track as usize)
+                .ok_or(ProtocolError::BadTrackIndex(track))?
+                .delete_key(row);
+            Ok(key_events.then_some(Event::KeyDeleted {
+                track: track as usize,
+                row,
+            }))
+        }
+        ServerCommand::SetRow(row) => Ok(Some(Event::SetRow(row))),
+        ServerCommand::Pause(flag) => Ok(Some(Event::Pause(flag))),
+        ServerCommand::SaveTracks => Ok(Some(Event::SaveTracks)),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::test_util::connect_pair;
+    use std::io::prelude::*;
+    use std::os::unix::net::UnixStream;
+
+    fn connect() -> (RocketClient<UnixStream>, UnixStream) {
+        connect_pair(|mut server| {
+            server.write_all(b"hello, demo!").unwrap();
+            let mut greeting = [0; 19];
+            server.read_exact(&mut greeting).unwrap();
+            assert_eq!(&greeting, b"hello, synctracker!");
+            server
+        })
+    }
+
+    #[test]
+    fn test_in_process_transport() {
+        let (mut rocket, mut server) = connect();
+        rocket.get_track_mut("test").unwrap();
+
+        let mut get_track = [0; 1 + 4 + 4];
+        server.read_exact(&mut get_track).unwrap();
+        assert_eq!(get_track, [2, 0, 0, 0, 4, b't', b'e', b's', b't']);
+
+        let mut buf = Vec::new();
+        ServerCommand::SetRow(5).encode(&mut buf);
+        ServerCommand::SetKey {
+            track: 0,
+            key: Key::new(5, 1.0, Interpolation::Linear),
+        }
+        .encode(&mut buf);
+        server.write_all(&buf).unwrap();
+
+        assert!(matches!(
+            rocket.poll_events().unwrap(),
+            Some(Event::SetRow(5))
+        ));
+        assert!(rocket.poll_events().unwrap().is_none());
+        assert_eq!(rocket.get_track("test").unwrap().get_value(5.), 1.0);
+    }
+
+    #[test]
+    fn test_key_events() {
+        let (mut rocket, mut server) = connect();
+        rocket.get_track_mut("a").unwrap();
+        rocket.get_track_mut("b").unwrap();
+        rocket.set_key_events(true);
+
+        let mut buf = Vec::new();
+        ServerCommand::SetKey {
+            track: 1,
+            key: Key::new(3, 1.0, Interpolation::Step),
+        }
+        .encode(&mut buf);
+        ServerCommand::DeleteKey { track: 1, row: 3 }.encode(&mut buf);
+        server.write_all(&buf).unwrap();
+
+        assert!(matches!(
+            rocket.poll_events().unwrap(),
+            Some(Event::KeyChanged { track: 1, row: 3 })
+        ));
+        assert!(matches!(
+            rocket.poll_events().unwrap(),
+            Some(Event::KeyDeleted { track: 1, row: 3 })
+        ));
+        assert_eq!(rocket.get_track_by_index(1).unwrap().get_name(), "b");
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let (mut rocket, mut server) = connect();
+
+        // DELETE_KEY track 0, row 0, but no tracks were requested
+        server.write_all(&[1, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert!(matches!(
+            rocket.poll_events(),
+            Err(Error::Protocol(ProtocolError::BadTrackIndex(0)))
+        ));
+
+        server.write_all(&[42]).unwrap();
+        assert!(matches!(
+            rocket.poll_events(),
+            Err(Error::Protocol(ProtocolError::UnknownCommand(42)))
+        ));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        rocket.set_unknown_command_hook(move |cmd| sender.send(cmd).unwrap());
+        server.write_all(&[42, 5]).unwrap();
+        assert!(matches!(
+            rocket.poll_events().unwrap(),
+            Some(Event::SaveTracks)
+        ));
+        assert_eq!(receiver.try_recv(), Ok(42));
+    }
+}
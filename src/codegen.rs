@@ -0,0 +1,231 @@
+//! This module generates Rust source code from tracks, for release builds without a track file.
+//!
+//! The generated module contains a static array of keys and an accessor function for every
+//! track, so a typo in a track name is a compile error instead of a panic. It only depends on
+//! [`Key`], [`Interpolation`] and [`track::sample`](crate::track::sample).
+//!
+//! The generated code has no inner attributes or doc comments, so it can be used with
+//! `include!`.
+//!
+//! # Examples
+//!
+//! In `build.rs`:
+//!
+//! ```rust,no_run
+//! # use rust_rocket::codegen;
+//! # use rust_rocket::formats::bundle;
+//! use std::fs::File;
+//! use std::path::Path;
+//!
+//! let tracks = bundle::read(File::open("tracks.bin").unwrap()).unwrap();
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! codegen::write_module(Path::new(&out_dir).join("sync.rs"), &tracks).unwrap();
+//! println!("cargo:rerun-if-changed=tracks.bin");
+//! ```
+//!
+//! In the demo:
+//!
+//! ```rust,ignore
+//! #[allow(dead_code)]
+//! mod sync {
+//!     include!(concat!(env!("OUT_DIR"), "/sync.rs"));
+//! }
+//!
+//! let [x, y] = [sync::cam_pos_x(row), sync::cam_pos_y(row)];
+//! ```
+//!
+//! A track named `cam:pos.x` becomes the static `CAM_POS_X` and the function `cam_pos_x`.
+//! All tracks are also listed in `TRACKS`, sorted by name, which can be played with
+//! [`StaticPlayer`](crate::StaticPlayer) without the `std` feature.
+use crate::interpolation::Interpolation;
+use crate::track::{Key, Track};
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Convert a track name to a lowercase Rust identifier.
+///
+/// Characters which can't be in identifiers are replaced with `_`. Keywords and `tracks`, which
+/// would clash with the generated `TRACKS`, get a `_` suffix.
+///
+/// # Examples
+///
+/// ```
+/// # use rust_rocket::codegen::identifier;
+/// assert_eq!(identifier("cam:pos.x"), "cam_pos_x");
+/// assert_eq!(identifier("2d:Fade"), "_2d_fade");
+/// assert_eq!(identifier("type"), "type_");
+/// assert_eq!(identifier("Tracks"), "tracks_");
+/// ```
+pub fn identifier(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    if ident == "_" || ident == "tracks" || KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+fn float(value: f32) -> String {
+    if value.is_nan() {
+        "f32::NAN".to_owned()
+    } else if value.is_infinite() {
+        if value > 0. {
+            "f32::INFINITY"
+        } else {
+            "f32::NEG_INFINITY"
+        }
+        .to_owned()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn key(key: &Key) -> String {
+    let mut code = format!(
+        "Key::new({}, {}, Interpolation::{:?})",
+        key.get_row(),
+        float(key.get_value()),
+        key.get_interpolation()
+    );
+    let [incoming, outgoing] = key.get_tangents();
+    if key.get_interpolation() == Interpolation::Bezier || incoming != 0. || outgoing != 0. {
+        write!(
+            code,
+            ".with_tangents({}, {})",
+            float(incoming),
+            float(outgoing)
+        )
+        .unwrap();
+    }
+    code
+}
+
+/// Generate a Rust module from tracks.
+///
+/// If several tracks map to the same [`identifier`], a number is appended to the later ones.
+pub fn generate(tracks: &[Track]) -> String {
+    let mut tracks: Vec<&Track> = tracks.iter().collect();
+    tracks.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+    let mut used = HashSet::new();
+    let idents: Vec<String> = tracks
+        .iter()
+        .map(|track| {
+            let base = identifier(track.get_name());
+            let mut ident = base.clone();
+            let mut n = 2;
+            while !used.insert(ident.clone()) {
+                ident = format!("{}_{}", base, n);
+                n += 1;
+            }
+            ident
+        })
+        .collect();
+
+    let mut code = String::new();
+    code.push_str("// Generated by rust-rocket. Do not edit.\n\n");
+    code.push_str("#[allow(unused_imports)]\n");
+    code.push_str("use rust_rocket::{interpolation::Interpolation, track::Key};\n");
+
+    for (track, ident) in tracks.iter().zip(&idents) {
+        let name = track.get_name();
+        write!(
+            code,
+            "\n// Keys of the track {name:?}.\npub static {upper}: &[Key] = &[\n",
+            name = name,
+            upper = ident.to_ascii_uppercase()
+        )
+        .unwrap();
+        for k in track.keys() {
+            writeln!(code, "    {},", key(k)).unwrap();
+        }
+        write!(
+            code,
+            "];\n\n// Get the value of the track {name:?} at a row.\n\
+             pub fn {ident}(row: f32) -> f32 {{\n    ::rust_rocket::track::sample({upper}, row)\n}}\n",
+            name = name,
+            ident = ident,
+            upper = ident.to_ascii_uppercase()
+        )
+        .unwrap();
+    }
+
+    code.push_str(
+        "\n// All tracks by name, sorted by name.\npub static TRACKS: &[(&str, &[Key])] = &[\n",
+    );
+    for (track, ident) in tracks.iter().zip(&idents) {
+        writeln!(
+            code,
+            "    ({:?}, {}),",
+            track.get_name(),
+            ident.to_ascii_uppercase()
+        )
+        .unwrap();
+    }
+    code.push_str("];\n");
+    code
+}
+
+/// Generate a Rust module from tracks and write it to `path`, see [`generate`].
+///
+/// The file is only written if its contents change, so that cargo doesn't rebuild needlessly.
+///
+/// # Errors
+///
+/// Any error from reading or writing `path`.
+pub fn write_module<P: AsRef<Path>>(path: P, tracks: &[Track]) -> io::Result<()> {
+    let code = generate(tracks);
+    match std::fs::read_to_string(&path) {
+        Ok(old) if old == code => Ok(()),
+        _ => std::fs::write(path, code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let mut a = Track::new("cam:pos.x");
+        a.set_key(Key::new(0, 1.0, Interpolation::Linear));
+        a.set_key(Key::new(8, -0.5, Interpolation::Bezier).with_tangents(0.25, 0.0));
+        let tracks = vec![Track::new("cam:pos:x"), a, Track::new("fn")];
+
+        let code = generate(&tracks);
+        assert!(code.contains(
+            "pub static CAM_POS_X: &[Key] = &[\n    \
+             Key::new(0, 1.0, Interpolation::Linear),\n    \
+             Key::new(8, -0.5, Interpolation::Bezier).with_tangents(0.25, 0.0),\n];"
+        ));
+        assert!(code.contains(
+            "pub fn cam_pos_x(row: f32) -> f32 {\n    ::rust_rocket::track::sample(CAM_POS_X, row)\n}"
+        ));
+        assert!(code.contains("pub fn cam_pos_x_2(row: f32)"));
+        assert!(code.contains("pub fn fn_(row: f32)"));
+        assert!(code.contains("    (\"cam:pos.x\", CAM_POS_X),\n    (\"cam:pos:x\", CAM_POS_X_2),"));
+        assert_eq!(float(f32::NEG_INFINITY), "f32::NEG_INFINITY");
+    }
+}
@@ -0,0 +1,108 @@
+//! This module contains bulk edits across many tracks, for restructuring a whole demo.
+//!
+//! Each function applies the [`Track`] method of the same name to every track, so that the
+//! tracks stay aligned with each other.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rust_rocket::edit;
+//! # use rust_rocket::track::Track;
+//! let mut tracks = vec![Track::new("cam:pos.x"), Track::new("cam:pos.y")];
+//! // Make room for a new 64 row section at row 128
+//! edit::insert_rows(&mut tracks, 128, 64);
+//! // Repeat the previous 64 rows in it
+//! let blocks = edit::copy_rows(&tracks, 64..128);
+//! edit::paste_rows(&mut tracks, 128, &blocks);
+//! ```
+use crate::track::{Block, Track};
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Insert `count` empty rows at `row` in every track, see [`Track::insert_rows`].
+pub fn insert_rows<'a, I: IntoIterator<Item = &'a mut Track>>(tracks: I, row: u32, count: u32) {
+    for track in tracks {
+        track.insert_rows(row, count);
+    }
+}
+
+/// Delete the rows in `rows` from every track, see [`Track::delete_rows`].
+pub fn delete_rows<'a, I: IntoIterator<Item = &'a mut Track>>(tracks: I, rows: Range<u32>) {
+    for track in tracks {
+        track.delete_rows(rows.clone());
+    }
+}
+
+/// Stretch or squash the rows in `rows` of every track, see [`Track::scale_rows`].
+///
+/// # Panics
+///
+/// If `factor` is not positive and finite.
+pub fn scale_rows<'a, I: IntoIterator<Item = &'a mut Track>>(
+    tracks: I,
+    rows: Range<u32>,
+    factor: f64,
+) {
+    for track in tracks {
+        track.scale_rows(rows.clone(), factor);
+    }
+}
+
+/// Reverse the order of the rows in `rows` of every track, see [`Track::reverse_rows`].
+pub fn reverse_rows<'a, I: IntoIterator<Item = &'a mut Track>>(tracks: I, rows: Range<u32>) {
+    for track in tracks {
+        track.reverse_rows(rows.clone());
+    }
+}
+
+/// Copy the keys in `rows` of every track, by track name. See [`Track::copy_rows`].
+pub fn copy_rows<'a, I: IntoIterator<Item = &'a Track>>(
+    tracks: I,
+    rows: Range<u32>,
+) -> HashMap<String, Block> {
+    tracks
+        .into_iter()
+        .map(|track| (track.get_name().to_owned(), track.copy_rows(rows.clone())))
+        .collect()
+}
+
+/// Paste blocks copied with [`copy_rows`] into the tracks with the same names, see
+/// [`Track::paste_rows`].
+///
+/// Tracks without a block are not changed.
+pub fn paste_rows<'a, I: IntoIterator<Item = &'a mut Track>>(
+    tracks: I,
+    row: u32,
+    blocks: &HashMap<String, Block>,
+) {
+    for track in tracks {
+        if let Some(block) = blocks.get(track.get_name()) {
+            track.paste_rows(row, block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::track::Key;
+
+    #[test]
+    fn test_tracks() {
+        let mut tracks = vec![Track::new("a"), Track::new("b")];
+        for (i, track) in tracks.iter_mut().enumerate() {
+            track.set_key(Key::new(2, i as f32, Interpolation::Step));
+        }
+
+        insert_rows(&mut tracks, 0, 2);
+        let blocks = copy_rows(&tracks[..1], 0..8);
+        paste_rows(&mut tracks, 10, &blocks);
+
+        assert_eq!(tracks[0].keys().len(), 2);
+        assert_eq!(tracks[0].key_at(14).unwrap().get_value(), 0.);
+        assert_eq!(tracks[1].keys().len(), 1);
+        assert_eq!(tracks[1].key_at(4).unwrap().get_value(), 1.);
+    }
+}
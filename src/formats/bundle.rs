@@ -0,0 +1,350 @@
+//! This module reads and writes rust-rocket's own bundle format.
+//!
+//! Unlike serializing `Vec<Track>` with serde, the bundle format is versioned and documented
+//! here, so files keep working across releases of this crate. A bundle is made of:
+//!
+//! | Field        | Encoding                                                        |
+//! |--------------|-----------------------------------------------------------------|
+//! | Magic        | The 4 bytes [`MAGIC`]                                           |
+//! | Version      | `u16`, currently [`VERSION`]                                    |
+//! | String table | String count, then for each string a byte length and UTF-8 data |
+//! | Tracks       | Track count, then for each track:                               |
+//! |              | - Part count, then the index of each part of the track's name   |
+//! |              |   in the string table (since version 3, see below)              |
+//! |              | - Key count, then for each key:                                 |
+//! |              | - Row delta to the previous key (or to 0 for the first key)     |
+//! |              | - Value as `f32`                                                |
+//! |              | - Interpolation as `u8`                                         |
+//! |              | - For [`Bezier`](Interpolation::Bezier) keys, the incoming and  |
+//! |              |   outgoing tangent as `f32` (since version 2)                   |
+//! | Checksum     | `u32` CRC-32 (IEEE) of all previous bytes                       |
+//!
+//! Track names are split at `:` into parts, and every distinct part is stored once in the
+//! string table, so groups like `cam:pos.x` and `cam:pos.y` share `cam`. Before version 3,
+//! every track had the index of its whole name instead of parts.
+//!
+//! Counts, lengths, indices and row deltas are unsigned LEB128 varints. Fixed size numbers are
+//! little endian. Bundles of all versions since [`MIN_VERSION`] can be read.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rust_rocket::formats::bundle;
+//! # use rust_rocket::track::Track;
+//! let tracks = vec![Track::new("test")];
+//! let bytes = bundle::to_bytes(&tracks);
+//! assert_eq!(bundle::from_bytes(&bytes).unwrap()[0].get_name(), "test");
+//! ```
+use crate::interpolation::Interpolation;
+use crate::track::{Key, Track};
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// The bytes every bundle starts with.
+pub const MAGIC: &[u8; 4] = b"RKTB";
+
+/// The bundle format version written by this version of the crate.
+pub const VERSION: u16 = 3;
+
+/// The oldest bundle format version this version of the crate can read.
+pub const MIN_VERSION: u16 = 1;
+
+#[derive(Debug, Error)]
+/// The `Error` Type for reading bundles.
+pub enum Error {
+    #[error("Failed to read bundle")]
+    /// Reading the input failed.
+    Io(#[source] io::Error),
+    #[error("Not a rust-rocket bundle")]
+    /// The input doesn't start with [`MAGIC`].
+    BadMagic,
+    #[error(
+        "Bundle version {0} is not supported, expected version {} to {}",
+        MIN_VERSION,
+        VERSION
+    )]
+    /// The bundle was written by an incompatible version of this crate.
+    UnsupportedVersion(u16),
+    #[error("Bundle checksum {found:#010x} doesn't match computed checksum {computed:#010x}")]
+    /// The bundle is corrupted.
+    ChecksumMismatch {
+        /// The checksum stored in the bundle.
+        found: u32,
+        /// The checksum of the bundle's contents.
+        computed: u32,
+    },
+    #[error("Bundle is malformed: {0}")]
+    /// The bundle's checksum is correct, but its contents are invalid.
+    Malformed(&'static str),
+}
+
+/// Compute the CRC-32 (IEEE) checksum of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// A cursor over the contents of a bundle.
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Malformed("unexpected end of bundle"));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u32, Error> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let b = self.take(1)?[0];
+            let bits = u32::from(b & 0x7f);
+            if bits.checked_shl(shift).map(|v| v >> shift) != Some(bits) {
+                return Err(Error::Malformed("varint overflow"));
+            }
+            value |= bits << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::Malformed("varint overflow"))
+    }
+}
+
+/// Encode tracks as a bundle.
+pub fn to_bytes(tracks: &[Track]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    buf.write_u16::<LittleEndian>(VERSION).unwrap();
+
+    let mut strings: Vec<&str> = Vec::new();
+    let names: Vec<Vec<u32>> = tracks
+        .iter()
+        .map(|track| {
+            track
+                .get_name()
+                .split(':')
+                .map(|part| {
+                    let index = strings.iter().position(|&s| s == part).unwrap_or_else(|| {
+                        strings.push(part);
+                        strings.len() - 1
+                    });
+                    index as u32
+                })
+                .collect()
+        })
+        .collect();
+
+    write_varint(&mut buf, strings.len() as u32);
+    for string in strings {
+        write_varint(&mut buf, string.len() as u32);
+        buf.extend_from_slice(string.as_bytes());
+    }
+
+    write_varint(&mut buf, tracks.len() as u32);
+    for (track, name) in tracks.iter().zip(names) {
+        write_varint(&mut buf, name.len() as u32);
+        for index in name {
+            write_varint(&mut buf, index);
+        }
+        write_varint(&mut buf, track.keys.len() as u32);
+        let mut previous = 0;
+        for key in &track.keys {
+            write_varint(&mut buf, key.row - previous);
+            buf.write_f32::<LittleEndian>(key.value).unwrap();
+            buf.push(key.interpolation as u8);
+            if key.interpolation == Interpolation::Bezier {
+                for &tangent in &key.tangents {
+                    buf.write_f32::<LittleEndian>(tangent).unwrap();
+                }
+            }
+            previous = key.row;
+        }
+    }
+
+    let checksum = crc32(&buf);
+    buf.write_u32::<LittleEndian>(checksum).unwrap();
+    buf
+}
+
+/// Decode tracks from a bundle.
+///
+/// # Errors
+///
+/// [`Error::BadMagic`] or [`Error::UnsupportedVersion`] if `bytes` is not a bundle this
+/// version of the crate can read, [`Error::ChecksumMismatch`] or [`Error::Malformed`] if it
+/// is corrupted.
+pub fn from_bytes(bytes: &[u8]) -> Result<Vec<Track>, Error> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::BadMagic);
+    }
+    let mut input = Input(&bytes[MAGIC.len()..]);
+    let version = LittleEndian::read_u16(input.take(2)?);
+    if !(MIN_VERSION..=VERSION).contains(&version) {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    let (contents, checksum) = bytes.split_at(bytes.len().saturating_sub(4).max(6));
+    let mut input = Input(checksum);
+    let found = LittleEndian::read_u32(input.take(4)?);
+    let computed = crc32(contents);
+    if found != computed {
+        return Err(Error::ChecksumMismatch { found, computed });
+    }
+
+    let mut input = Input(&contents[6..]);
+    let mut strings = Vec::new();
+    for _ in 0..input.varint()? {
+        let len = input.varint()? as usize;
+        let string = std::str::from_utf8(input.take(len)?)
+            .map_err(|_| Error::Malformed("string is not UTF-8"))?;
+        strings.push(string);
+    }
+
+    let mut tracks = Vec::new();
+    let string = |input: &mut Input| {
+        strings
+            .get(input.varint()? as usize)
+            .copied()
+            .ok_or(Error::Malformed("string index out of bounds"))
+    };
+    for _ in 0..input.varint()? {
+        let name = if version >= 3 {
+            let mut parts = Vec::new();
+            for _ in 0..input.varint()? {
+                parts.push(string(&mut input)?);
+            }
+            parts.join(":")
+        } else {
+            string(&mut input)?.to_owned()
+        };
+        let mut track = Track::new(name);
+        let mut row = 0u32;
+        for i in 0..input.varint()? {
+            let delta = input.varint()?;
+            if i > 0 && delta == 0 {
+                return Err(Error::Malformed("duplicate row"));
+            }
+            row = row
+                .checked_add(delta)
+                .ok_or(Error::Malformed("row overflow"))?;
+            let value = LittleEndian::read_f32(input.take(4)?);
+            let interpolation = Interpolation::from(input.take(1)?[0]);
+            let mut key = Key::new(row, value, interpolation);
+            if version >= 2 && interpolation == Interpolation::Bezier {
+                let tangents = input.take(8)?;
+                key = key.with_tangents(
+                    LittleEndian::read_f32(tangents),
+                    LittleEndian::read_f32(&tangents[4..]),
+                );
+            }
+            track.keys.push(key);
+        }
+        tracks.push(track);
+    }
+
+    if !input.0.is_empty() {
+        return Err(Error::Malformed("trailing bytes"));
+    }
+    Ok(tracks)
+}
+
+/// Write tracks as a bundle.
+///
+/// # Errors
+///
+/// Any error from `writer`.
+pub fn write<W: Write>(mut writer: W, tracks: &[Track]) -> io::Result<()> {
+    writer.write_all(&to_bytes(tracks))
+}
+
+/// Read tracks from a bundle, see [`from_bytes`].
+///
+/// # Errors
+///
+/// [`Error::Io`] if reading fails, or any error from [`from_bytes`].
+pub fn read<R: Read>(mut reader: R) -> Result<Vec<Track>, Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(Error::Io)?;
+    from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut a = Track::new("cam:pos.x");
+        a.set_key(Key::new(0, 1.0, Interpolation::Linear));
+        a.set_key(Key::new(130, -0.25, Interpolation::Step));
+        a.set_key(Key::new(140, 2.0, Interpolation::Bezier).with_tangents(0.5, -1.0));
+        a.set_key(Key::new(u32::MAX, 3.0, Interpolation::Ramp));
+        let tracks = vec![a, Track::new(""), Track::new("cam:pos.x")];
+
+        let bytes = to_bytes(&tracks);
+        let read = from_bytes(&bytes).unwrap();
+        assert_eq!(read.len(), tracks.len());
+        for (a, b) in tracks.iter().zip(&read) {
+            assert_eq!(a.get_name(), b.get_name());
+            assert_eq!(a.keys, b.keys);
+        }
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+        // Name parts are only stored once
+        let tracks = [Track::new("cam:pos.x"), Track::new("cam:pos.y")];
+        let bytes = to_bytes(&tracks);
+        assert_eq!(bytes.windows(3).filter(|w| w == b"cam").count(), 1);
+        assert_eq!(from_bytes(&bytes).unwrap()[1].get_name(), "cam:pos.y");
+    }
+
+    #[test]
+    fn test_version_2() {
+        // One string, one track with the string's index as its whole name and no keys
+        let mut bytes = b"RKTB\x02\x00\x01\x05cam:x\x01\x00\x00".to_vec();
+        let checksum = crc32(&bytes);
+        bytes.write_u32::<LittleEndian>(checksum).unwrap();
+        let tracks = from_bytes(&bytes).unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].get_name(), "cam:x");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut bytes = to_bytes(&[Track::new("test")]);
+        assert!(matches!(from_bytes(b"RKT"), Err(Error::BadMagic)));
+        assert!(matches!(from_bytes(&bytes[..6]), Err(Error::Malformed(_))));
+
+        bytes[4] = 4;
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(Error::UnsupportedVersion(4))
+        ));
+
+        bytes[4] = 3;
+        bytes[8] ^= 1;
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+    }
+}
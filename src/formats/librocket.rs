@@ -0,0 +1,302 @@
+//! This module reads and writes the per-track binary files of the reference C librocket.
+//!
+//! librocket saves each track to its own file when the editor asks it to, and loads them when
+//! built with `SYNC_PLAYER`. A track file contains the number of keys as a `u32`, followed by
+//! each key's row as a `u32`, value as an `f32` and interpolation type as a `u8`.
+//! librocket writes these in native byte order, this module assumes little endian.
+//!
+//! Files are named `<base>_<track name>.track`, where `base` is the path given to
+//! `sync_create_device` and characters other than ASCII letters, digits, `.`, `_` and `/` in
+//! the track name are escaped as `-XX` in hex. For example, the track `camera:pos.x` of the device
+//! `data/sync` is saved in `data/sync_camera-3Apos.x.track`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # use rust_rocket::formats::librocket;
+//! // Load data/sync_*.track
+//! let player = librocket::load_player("data/sync").unwrap();
+//! ```
+use crate::interpolation::Interpolation;
+use crate::player::RocketPlayer;
+use crate::track::{Key, Track};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const EXTENSION: &str = ".track";
+
+#[derive(Debug, Error)]
+/// The `Error` Type for reading and writing librocket track files.
+pub enum Error {
+    #[error("Failed to read or write track file {0:?}")]
+    /// Reading or writing a file failed, or a track file is truncated.
+    Io(PathBuf, #[source] io::Error),
+    #[error("Track file name {0:?} is not a valid escaped track name")]
+    /// A file matching `<base>_*.track` has an invalid escape sequence or is not UTF-8.
+    InvalidFileName(OsString),
+}
+
+/// Check if librocket keeps a byte of a track name as is in file names, like its
+/// `valid_path_char`.
+fn valid_path_char(b: u8) -> bool {
+    matches!(b, b'.' | b'_' | b'/') || b.is_ascii_alphanumeric()
+}
+
+/// Escape a track name like librocket does for file names.
+///
+/// `/` is not escaped, so tracks with `/` in their names are saved in subdirectories.
+pub fn encode_name(name: &str) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut encoded = String::with_capacity(name.len());
+    for &b in name.as_bytes() {
+        if valid_path_char(b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push('-');
+            encoded.push(HEX[usize::from(b >> 4)] as char);
+            encoded.push(HEX[usize::from(b & 0xf)] as char);
+        }
+    }
+    encoded
+}
+
+/// Reverse [`encode_name`]. Returns `None` if `encoded` is not a valid escaped name.
+pub fn decode_name(encoded: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut iter = encoded.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'-' {
+            let hex = [iter.next()?, iter.next()?];
+            // from_str_radix also accepts a sign
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Get the path of a track's file, like librocket's `sync_track_path`.
+pub fn track_path<P: AsRef<Path>>(base: P, name: &str) -> PathBuf {
+    let mut path = base.as_ref().as_os_str().to_owned();
+    path.push("_");
+    path.push(encode_name(name));
+    path.push(EXTENSION);
+    path.into()
+}
+
+/// Read the keys of a track file into a new track called `name`.
+///
+/// # Errors
+///
+/// Any error from `reader`. A truncated file is an error of kind
+/// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof).
+pub fn read_track<R: Read>(mut reader: R, name: &str) -> io::Result<Track> {
+    let mut track = Track::new(name);
+    let count = reader.read_u32::<LittleEndian>()?;
+    for _ in 0..count {
+        let row = reader.read_u32::<LittleEndian>()?;
+        let value = reader.read_f32::<LittleEndian>()?;
+        let interpolation = Interpolation::from(reader.read_u8()?);
+        track.set_key(Key::new(row, value, interpolation));
+    }
+    Ok(track)
+}
+
+/// Write the keys of a track in the track file format.
+///
+/// Extended interpolation types are written as their [`fallback`](Interpolation::fallback),
+/// because librocket only knows the basic types.
+///
+/// # Errors
+///
+/// Any error from `writer`.
+pub fn write_track<W: Write>(mut writer: W, track: &Track) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(track.keys.len() as u32)?;
+    for key in &track.keys {
+        writer.write_u32::<LittleEndian>(key.row)?;
+        writer.write_f32::<LittleEndian>(key.value)?;
+        writer.write_u8(key.interpolation.fallback() as u8)?;
+    }
+    Ok(())
+}
+
+/// Read all track files of the device `base`, see the [module documentation](self).
+///
+/// The tracks are sorted by name.
+///
+/// # Errors
+///
+/// [`Error::Io`] if the directory or a track file can't be read, or [`Error::InvalidFileName`]
+/// if a file name can't be decoded.
+pub fn read_tracks<P: AsRef<Path>>(base: P) -> Result<Vec<Track>, Error> {
+    let base = base.as_ref();
+    let dir = match base.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut prefix = base.file_name().unwrap_or_default().to_owned();
+    prefix.push("_");
+    let prefix = prefix.to_string_lossy();
+
+    let mut tracks = Vec::new();
+    read_dir_tracks(dir, "", &prefix, &mut tracks)?;
+    tracks.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+    Ok(tracks)
+}
+
+/// Read the track files in `dir`, whose path relative to the base directory is `relative`.
+///
+/// Subdirectories which may contain tracks with `/` in their names are read recursively.
+fn read_dir_tracks(
+    dir: &Path,
+    relative: &str,
+    prefix: &str,
+    tracks: &mut Vec<Track>,
+) -> Result<(), Error> {
+    let entries = fs::read_dir(dir).map_err(|e| Error::Io(dir.to_owned(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::Io(dir.to_owned(), e))?;
+        let file_name = entry.file_name();
+        let path = entry.path();
+        let relative = match file_name.to_str() {
+            Some(name) => format!("{}{}", relative, name),
+            None => continue,
+        };
+
+        if path.is_dir() {
+            if relative.starts_with(prefix) {
+                read_dir_tracks(&path, &format!("{}/", relative), prefix, tracks)?;
+            }
+            continue;
+        }
+
+        let encoded = match relative
+            .strip_prefix(prefix)
+            .and_then(|f| f.strip_suffix(EXTENSION))
+        {
+            Some(encoded) => encoded,
+            None => continue,
+        };
+        let name = decode_name(encoded).ok_or_else(|| Error::InvalidFileName(file_name.clone()))?;
+
+        let file = File::open(&path).map_err(|e| Error::Io(path.clone(), e))?;
+        let track = read_track(BufReader::new(file), &name).map_err(|e| Error::Io(path, e))?;
+        tracks.push(track);
+    }
+    Ok(())
+}
+
+/// Write each track to its own file for the device `base`, see the
+/// [module documentation](self).
+///
+/// # Errors
+///
+/// [`Error::Io`] if a track file can't be written.
+pub fn write_tracks<P: AsRef<Path>>(base: P, tracks: &[Track]) -> Result<(), Error> {
+    for track in tracks {
+        let path = track_path(&base, track.get_name());
+        // Like librocket, create the directories of tracks with `/` in their names
+        if track.get_name().contains('/') {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|e| Error::Io(dir.to_owned(), e))?;
+            }
+        }
+        File::create(&path)
+            .map(BufWriter::new)
+            .and_then(|mut writer| {
+                write_track(&mut writer, track)?;
+                writer.flush()
+            })
+            .map_err(|e| Error::Io(path, e))?;
+    }
+    Ok(())
+}
+
+/// Construct a [`RocketPlayer`] from all track files of the device `base`.
+///
+/// # Errors
+///
+/// Same as [`read_tracks`].
+pub fn load_player<P: AsRef<Path>>(base: P) -> Result<RocketPlayer, Error> {
+    read_tracks(base).map(RocketPlayer::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_file() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(1, 0.5, Interpolation::Linear));
+        track.set_key(Key::new(256, -2.0, Interpolation::Smooth));
+
+        let mut buf = Vec::new();
+        write_track(&mut buf, &track).unwrap();
+        assert_eq!(
+            buf,
+            [
+                2, 0, 0, 0, //
+                1, 0, 0, 0, 0, 0, 0, 0x3f, 1, //
+                0, 1, 0, 0, 0, 0, 0, 0xc0, 2,
+            ]
+        );
+        assert_eq!(read_track(&buf[..], "test").unwrap().keys, track.keys);
+
+        let err = read_track(&buf[..buf.len() - 1], "test").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut track = Track::new("test");
+        track.set_key(Key::new(0, 1.0, Interpolation::EaseIn));
+        let mut buf = Vec::new();
+        write_track(&mut buf, &track).unwrap();
+        assert_eq!(
+            read_track(&buf[..], "test").unwrap().keys,
+            [Key::new(0, 1.0, Interpolation::Ramp)]
+        );
+    }
+
+    #[test]
+    fn test_directory() {
+        assert_eq!(encode_name("camera:pos.x"), "camera-3Apos.x");
+        assert_eq!(decode_name("camera-3Apos.x").unwrap(), "camera:pos.x");
+        assert_eq!(decode_name("bad-3"), None);
+        assert_eq!(decode_name("bad-+1"), None);
+        assert_eq!(decode_name("bad--1"), None);
+
+        let dir = std::env::temp_dir().join(format!("rust-rocket-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("sync");
+
+        let mut a = Track::new("cam:pos x");
+        a.set_key(Key::new(3, 1.0, Interpolation::Step));
+        let b = Track::new("ä");
+        let mut c = Track::new("scene/cam:x");
+        c.set_key(Key::new(0, 2.0, Interpolation::Step));
+        write_tracks(&base, &[a, b, c]).unwrap();
+        assert!(track_path(&base, "cam:pos x").ends_with("sync_cam-3Apos-20x.track"));
+        assert!(track_path(&base, "scene/cam:x").ends_with("sync_scene/cam-3Ax.track"));
+        File::create(dir.join("other_x.track")).unwrap();
+
+        let tracks = read_tracks(&base).unwrap();
+        let player = load_player(&base).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(tracks[0].get_name(), "cam:pos x");
+        assert_eq!(tracks[1].get_name(), "scene/cam:x");
+        assert_eq!(tracks[1].get_value(0.), 2.0);
+        assert_eq!(tracks[2].get_name(), "ä");
+        assert_eq!(player.get_track("cam:pos x").unwrap().get_value(5.), 1.0);
+    }
+}
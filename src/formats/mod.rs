@@ -0,0 +1,9 @@
+//! This module contains readers and writers for track file formats.
+//!
+//! Tracks saved with [`RocketClient::save_tracks`](crate::RocketClient::save_tracks) are best
+//! stored as a [`bundle`]. The other formats are for interoperating with other Rocket tools.
+
+pub mod bundle;
+pub mod librocket;
+#[cfg(feature = "rocket-xml")]
+pub mod rocket_xml;
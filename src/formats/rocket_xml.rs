@@ -0,0 +1,287 @@
+//! This module reads and writes the GNU Rocket editor's `.rocket` XML project format.
+//!
+//! This module requires the `rocket-xml` feature.
+//!
+//! A project looks like this:
+//!
+//! ```xml
+//! <?xml version="1.0" encoding="utf-8"?>
+//! <sync rows="128">
+//!     <tracks>
+//!         <track name="camera:pos.x">
+//!             <key row="0" value="1.5" interpolation="1"/>
+//!         </track>
+//!     </tracks>
+//! </sync>
+//! ```
+//!
+//! Elements and attributes other than `track` and `key` are ignored when reading, so projects
+//! from alternative editors such as RocketEditor can be read as well.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # use rust_rocket::RocketPlayer;
+//! # use rust_rocket::formats::rocket_xml;
+//! use std::fs::File;
+//! use std::io::BufReader;
+//!
+//! let file = BufReader::new(File::open("demo.rocket").unwrap());
+//! let player = RocketPlayer::new(rocket_xml::read(file).unwrap());
+//! ```
+use crate::interpolation::Interpolation;
+use crate::track::{Key, Track};
+
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The row count written to projects with fewer rows. This is the editor's default.
+const MIN_ROWS: u32 = 128;
+
+#[derive(Debug, Error)]
+/// The `Error` Type for reading and writing Rocket XML.
+pub enum Error {
+    #[error("Failed to parse Rocket XML")]
+    /// The input is not well-formed XML, or reading it failed.
+    Xml(#[source] quick_xml::Error),
+    #[error("Failed to write Rocket XML")]
+    /// Writing the output failed.
+    Io(#[source] std::io::Error),
+    #[error("Element <{element}> is missing attribute {attribute:?}")]
+    /// A `track` or `key` element is missing a required attribute.
+    MissingAttribute {
+        /// Name of the element.
+        element: &'static str,
+        /// Name of the missing attribute.
+        attribute: &'static str,
+    },
+    #[error("Attribute {attribute:?} of element <{element}> has invalid value {value:?}")]
+    /// An attribute of a `key` element could not be parsed as a number.
+    InvalidAttribute {
+        /// Name of the element.
+        element: &'static str,
+        /// Name of the attribute.
+        attribute: &'static str,
+        /// The invalid value.
+        value: String,
+    },
+    #[error("Element <key> is not inside a <track>")]
+    /// A `key` element was found outside of a `track` element.
+    KeyOutsideTrack,
+}
+
+fn attribute(
+    element: &BytesStart,
+    element_name: &'static str,
+    name: &'static str,
+) -> Result<String, Error> {
+    let attribute = element
+        .try_get_attribute(name)
+        .map_err(|e| Error::Xml(e.into()))?
+        .ok_or(Error::MissingAttribute {
+            element: element_name,
+            attribute: name,
+        })?;
+    let value = attribute
+        .normalized_value(XmlVersion::Implicit1_0)
+        .map_err(Error::Xml)?;
+    Ok(value.into_owned())
+}
+
+fn parse_attribute<T: FromStr>(
+    element: &BytesStart,
+    element_name: &'static str,
+    name: &'static str,
+) -> Result<T, Error> {
+    let value = attribute(element, element_name, name)?;
+    value.trim().parse().map_err(|_| Error::InvalidAttribute {
+        element: element_name,
+        attribute: name,
+        value,
+    })
+}
+
+fn parse_key(element: &BytesStart) -> Result<Key, Error> {
+    Ok(Key::new(
+        parse_attribute(element, "key", "row")?,
+        parse_attribute(element, "key", "value")?,
+        Interpolation::from(parse_attribute::<u8>(element, "key", "interpolation")?),
+    ))
+}
+
+/// Read tracks from a `.rocket` project.
+///
+/// # Errors
+///
+/// [`Error::Xml`] if the input is not valid XML, or any of the other errors if a `track` or
+/// `key` element is invalid.
+pub fn read<R: BufRead>(reader: R) -> Result<Vec<Track>, Error> {
+    let mut reader = Reader::from_reader(reader);
+    let mut buf = Vec::new();
+    let mut tracks = Vec::new();
+    let mut track: Option<Track> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf).map_err(Error::Xml)? {
+            Event::Start(e) if e.name().as_ref() == "track" => {
+                track = Some(Track::new(attribute(&e, "track", "name")?));
+            }
+            Event::Empty(e) if e.name().as_ref() == "track" => {
+                tracks.push(Track::new(attribute(&e, "track", "name")?));
+            }
+            Event::End(e) if e.name().as_ref() == "track" => {
+                tracks.extend(track.take());
+            }
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == "key" => {
+                let key = parse_key(&e)?;
+                track.as_mut().ok_or(Error::KeyOutsideTrack)?.set_key(key);
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(tracks)
+}
+
+/// Read tracks from a `.rocket` project in a string, see [`read`].
+///
+/// # Errors
+///
+/// Same as [`read`].
+pub fn from_str(xml: &str) -> Result<Vec<Track>, Error> {
+    read(xml.as_bytes())
+}
+
+/// Write tracks as a `.rocket` project.
+///
+/// The project's row count is set to one past the last key of all tracks, but at least 128.
+/// Extended interpolation types are written as their [`fallback`](Interpolation::fallback),
+/// because the editor only knows the basic types.
+///
+/// # Errors
+///
+/// [`Error::Io`] if writing fails.
+pub fn write<W: Write>(mut writer: W, tracks: &[Track]) -> Result<(), Error> {
+    let rows = tracks
+        .iter()
+        .filter_map(|t| t.keys.last())
+        .map(|k| k.row.saturating_add(1))
+        .fold(MIN_ROWS, u32::max);
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str(&format!("<sync rows=\"{}\">\n", rows));
+    xml.push_str("\t<tracks>\n");
+    for track in tracks {
+        xml.push_str(&format!(
+            "\t\t<track name=\"{}\">\n",
+            escape(track.get_name())
+        ));
+        for key in &track.keys {
+            xml.push_str(&format!(
+                "\t\t\t<key row=\"{}\" value=\"{}\" interpolation=\"{}\"/>\n",
+                key.row,
+                key.value,
+                key.interpolation.fallback() as u8
+            ));
+        }
+        xml.push_str("\t\t</track>\n");
+    }
+    xml.push_str("\t</tracks>\n");
+    xml.push_str("</sync>\n");
+
+    writer.write_all(xml.as_bytes()).map_err(Error::Io)
+}
+
+/// Write tracks as a `.rocket` project into a string, see [`write`].
+pub fn to_string(tracks: &[Track]) -> String {
+    let mut buf = Vec::new();
+    write(&mut buf, tracks).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_editor_project() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<sync rows="10000">
+    <tracks>
+        <track name="cam:pos.x" folded="0" color="ffb27474">
+            <key row="0" value="1.000000" interpolation="1"/>
+            <key row="8" value="-3.5" interpolation="0"/>
+        </track>
+        <track name="a &amp; b"/>
+    </tracks>
+    <bookmarks>
+        <bookmark row="16"/>
+    </bookmarks>
+</sync>"#;
+        let tracks = from_str(xml).unwrap();
+
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].get_name(), "cam:pos.x");
+        assert_eq!(
+            tracks[0].keys,
+            [
+                Key::new(0, 1.0, Interpolation::Linear),
+                Key::new(8, -3.5, Interpolation::Step)
+            ]
+        );
+        assert_eq!(tracks[0].get_value(4.), -1.25);
+        assert_eq!(tracks[1].get_name(), "a & b");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut track = Track::new("<quoted \"name\">");
+        track.set_key(Key::new(0, 0.1, Interpolation::Smooth));
+        track.set_key(Key::new(200, 1e-7, Interpolation::Ramp));
+        let tracks = vec![track, Track::new("empty")];
+
+        let mut extended = Track::new("extended");
+        extended.set_key(Key::new(0, 1.0, Interpolation::EaseIn));
+        let read = from_str(&to_string(&[extended])).unwrap();
+        assert_eq!(read[0].keys, [Key::new(0, 1.0, Interpolation::Ramp)]);
+
+        let xml = to_string(&tracks);
+        assert!(xml.contains("<sync rows=\"201\">"));
+
+        let read = from_str(&xml).unwrap();
+        assert_eq!(read.len(), 2);
+        for (a, b) in tracks.iter().zip(&read) {
+            assert_eq!(a.get_name(), b.get_name());
+            assert_eq!(a.keys, b.keys);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            from_str(r#"<track name="a"><key row="x" value="0" interpolation="0"/></track>"#),
+            Err(Error::InvalidAttribute {
+                attribute: "row",
+                ..
+            })
+        ));
+        assert!(matches!(
+            from_str(r#"<track><key row="0" value="0" interpolation="0"/></track>"#),
+            Err(Error::MissingAttribute {
+                element: "track",
+                attribute: "name",
+            })
+        ));
+        assert!(matches!(
+            from_str(r#"<key row="0" value="0" interpolation="0"/>"#),
+            Err(Error::KeyOutsideTrack)
+        ));
+    }
+}
@@ -1,8 +1,14 @@
 //! This module contains anything related to interpolation.
+//!
+//! GNU Rocket only knows the basic types [`Step`](Interpolation::Step),
+//! [`Linear`](Interpolation::Linear), [`Smooth`](Interpolation::Smooth) and
+//! [`Ramp`](Interpolation::Ramp). The other types are extensions supported by some editors.
+//! When talking to a peer which only supports the basic types, use [`Encoding::Basic`] so that
+//! extended types are replaced with their [`fallback`](Interpolation::fallback).
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 /// The `Interpolation` Type.
 /// This represents the various forms of interpolation that can be performed.
 pub enum Interpolation {
@@ -14,8 +20,30 @@ pub enum Interpolation {
     Smooth = 2,
     /// `t.powi(2)`
     Ramp = 3,
+    /// `t.powi(3)`
+    EaseIn = 4,
+    /// `1 - (1 - t).powi(3)`
+    EaseOut = 5,
+    /// `EaseIn` for the first half and `EaseOut` for the second half.
+    EaseInOut = 6,
+    /// A Catmull-Rom spline through the neighbouring keys.
+    CatmullRom = 7,
+    /// A cubic Bezier curve, shaped by the tangents of the two keys.
+    /// See [`Key::with_tangents`](crate::track::Key::with_tangents).
+    Bezier = 8,
 }
 
+/// Which interpolation types a peer supports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    /// Only the types supported by GNU Rocket.
+    Basic,
+    /// All types known to this crate.
+    Extended,
+}
+
+/// Unknown types are treated as [`Interpolation::Step`], like the reference C implementation
+/// does.
 impl From<u8> for Interpolation {
     fn from(raw: u8) -> Interpolation {
         match raw {
@@ -23,14 +51,28 @@ impl From<u8> for Interpolation {
             1 => Interpolation::Linear,
             2 => Interpolation::Smooth,
             3 => Interpolation::Ramp,
+            4 => Interpolation::EaseIn,
+            5 => Interpolation::EaseOut,
+            6 => Interpolation::EaseInOut,
+            7 => Interpolation::CatmullRom,
+            8 => Interpolation::Bezier,
             _ => Interpolation::Step,
         }
     }
 }
 
+/// `f32::powi` needs `std`.
+fn cube(t: f32) -> f32 {
+    t * t * t
+}
+
 impl Interpolation {
     /// This performs the interpolation.
     ///
+    /// [`CatmullRom`](Interpolation::CatmullRom) and [`Bezier`](Interpolation::Bezier) depend on
+    /// more than two keys, so [`Track::get_value`](crate::track::Track::get_value) should be used
+    /// for them. Here they behave like [`Smooth`](Interpolation::Smooth).
+    ///
     /// # Examples
     ///
     /// ```
@@ -46,8 +88,71 @@ impl Interpolation {
         match *self {
             Interpolation::Step => 0.0,
             Interpolation::Linear => t,
-            Interpolation::Smooth => t * t * (3.0 - 2.0 * t),
-            Interpolation::Ramp => t.powi(2),
+            Interpolation::Smooth | Interpolation::CatmullRom | Interpolation::Bezier => {
+                t * t * (3.0 - 2.0 * t)
+            }
+            Interpolation::Ramp => t * t,
+            Interpolation::EaseIn => cube(t),
+            Interpolation::EaseOut => 1.0 - cube(1.0 - t),
+            Interpolation::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * cube(t)
+                } else {
+                    1.0 - 4.0 * cube(1.0 - t)
+                }
+            }
+        }
+    }
+
+    /// Check if GNU Rocket supports this type.
+    pub fn is_basic(&self) -> bool {
+        (*self as u8) <= Interpolation::Ramp as u8
+    }
+
+    /// Get the closest basic type, see [`is_basic`](Interpolation::is_basic).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rust_rocket::interpolation::Interpolation;
+    /// assert_eq!(Interpolation::EaseIn.fallback(), Interpolation::Ramp);
+    /// assert_eq!(Interpolation::Linear.fallback(), Interpolation::Linear);
+    /// ```
+    pub fn fallback(&self) -> Interpolation {
+        match *self {
+            Interpolation::EaseIn => Interpolation::Ramp,
+            Interpolation::EaseOut
+            | Interpolation::EaseInOut
+            | Interpolation::CatmullRom
+            | Interpolation::Bezier => Interpolation::Smooth,
+            basic => basic,
+        }
+    }
+
+    /// Get the type to send to a peer which supports `encoding`.
+    pub fn encode(&self, encoding: Encoding) -> Interpolation {
+        match encoding {
+            Encoding::Basic => self.fallback(),
+            Encoding::Extended => *self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_types() {
+        for raw in 0..=9 {
+            let interpolation = Interpolation::from(raw);
+            assert_eq!(interpolation as u8, raw % 9);
+            assert!(interpolation.encode(Encoding::Basic).is_basic());
+            assert_eq!(interpolation.interpolate(0.), 0.);
+            if interpolation != Interpolation::Step {
+                assert_eq!(interpolation.interpolate(1.), 1.);
+            }
         }
+        assert_eq!(Interpolation::EaseInOut.interpolate(0.25), 0.0625);
     }
 }
@@ -1,10 +1,65 @@
 //! This is the rust-rocket crate.
 //! It is designed to work as a client library for GNU Rocket.
+//!
+//! # `no_std`
+//!
+//! Everything which needs the standard library is behind the default `std` feature. Without it,
+//! the crate is `no_std` and doesn't allocate, and only contains [`Key`](track::Key),
+//! [`Interpolation`](interpolation::Interpolation), [`Timing`](timing::Timing) and
+//! [`StaticPlayer`], which plays keys from static arrays like the ones generated by `codegen`.
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "tokio")]
+pub mod async_client;
+#[cfg(feature = "std")]
+pub mod bake;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "std")]
 pub mod client;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(feature = "std")]
+pub mod edit;
+#[cfg(feature = "std")]
+pub mod formats;
 pub mod interpolation;
+#[cfg(feature = "std")]
+pub mod mock;
+#[cfg(feature = "std")]
+pub mod playback;
+#[cfg(feature = "std")]
 pub mod player;
+#[cfg(feature = "std")]
+pub mod protocol;
+#[cfg(feature = "std")]
+pub mod reconnect;
+#[cfg(feature = "std")]
+pub mod server;
+pub mod static_player;
+#[cfg(feature = "std")]
+pub mod sync;
+#[cfg(all(test, feature = "std"))]
+mod test_util;
+#[cfg(feature = "std")]
+pub mod time_source;
+pub mod timing;
 pub mod track;
+#[cfg(feature = "std")]
+pub mod transport;
+#[cfg(feature = "std")]
+pub mod vector;
 
+#[cfg(feature = "std")]
 pub use client::RocketClient;
+#[cfg(feature = "std")]
+pub use playback::Playback;
+#[cfg(feature = "std")]
 pub use player::RocketPlayer;
+#[cfg(feature = "std")]
+pub use reconnect::ReconnectingClient;
+#[cfg(feature = "std")]
+pub use server::RocketServer;
+pub use static_player::StaticPlayer;
+#[cfg(feature = "std")]
+pub use sync::SyncSource;
@@ -0,0 +1,264 @@
+//! This module contains [`MockTracker`], an in-process stand-in for the Rocket tracker.
+//!
+//! It is meant for testing code that uses [`RocketClient`](crate::RocketClient) without
+//! launching an editor.
+use crate::protocol::{ClientCommand, Command, Decoder, ServerCommand};
+use crate::protocol::{CLIENT_GREETING, SERVER_GREETING};
+use crate::track::Key;
+use crate::transport::Transport;
+
+use std::io::{Error, ErrorKind, Result};
+use std::net::{TcpListener, TcpStream};
+
+/// The tracker side of a single Rocket connection.
+///
+/// The tracker records the tracks and rows the demo asks for, and lets a test send editor
+/// commands to the demo.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_rocket::{RocketClient, mock::MockTracker};
+/// # use rust_rocket::client::Event;
+/// # use rust_rocket::interpolation::Interpolation;
+/// # use rust_rocket::track::Key;
+/// use std::net::TcpListener;
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let port = listener.local_addr().unwrap().port();
+/// let tracker = std::thread::spawn(move || MockTracker::accept(&listener).unwrap());
+///
+/// let mut rocket = RocketClient::connect("127.0.0.1", port).unwrap();
+/// let mut tracker = tracker.join().unwrap();
+///
+/// rocket.get_track_mut("test").unwrap();
+/// let track = tracker.wait_for_track("test").unwrap();
+/// tracker.set_key(track, Key::new(0, 1.0, Interpolation::Step)).unwrap();
+/// tracker.pause(false).unwrap();
+///
+/// while !matches!(rocket.poll_events().unwrap(), Some(Event::Pause(false))) {}
+/// assert_eq!(rocket.get_track("test").unwrap().get_value(0.), 1.0);
+/// ```
+#[derive(Debug)]
+pub struct MockTracker<S: Transport = TcpStream> {
+    stream: S,
+    decoder: Decoder<ClientCommand>,
+    tracks: Vec<String>,
+    rows: Vec<u32>,
+}
+
+impl MockTracker<TcpStream> {
+    /// Accept a demo connection from `listener` and perform the handshake.
+    ///
+    /// This blocks until a demo connects.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error from accepting the connection or from the handshake.
+    /// A greeting mismatch is reported as [`ErrorKind::InvalidData`].
+    pub fn accept(listener: &TcpListener) -> Result<Self> {
+        let (stream, _) = listener.accept()?;
+        Self::with_transport(stream)
+    }
+}
+
+impl<S: Transport> MockTracker<S> {
+    /// Perform the handshake over an already connected [`Transport`].
+    ///
+    /// This blocks until the demo has sent its greeting, so the demo side must be driven from
+    /// another thread.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error from the handshake.
+    /// A greeting mismatch is reported as [`ErrorKind::InvalidData`].
+    pub fn with_transport(mut stream: S) -> Result<Self> {
+        let mut buf = [0; 19];
+        stream.read_exact(&mut buf)?;
+        if &buf != CLIENT_GREETING {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("The demo greeting {:?} wasn't correct", buf),
+            ));
+        }
+
+        stream.write_all(SERVER_GREETING)?;
+        stream.set_nonblocking(true)?;
+
+        Ok(Self {
+            stream,
+            decoder: Decoder::new(),
+            tracks: Vec::new(),
+            rows: Vec::new(),
+        })
+    }
+
+    /// Receive and record all commands the demo has sent so far.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error other than [`ErrorKind::WouldBlock`], [`ErrorKind::UnexpectedEof`] if the
+    /// demo disconnected, or [`ErrorKind::InvalidData`] if the demo sent an invalid command.
+    pub fn update(&mut self) -> Result<()> {
+        let mut chunk = [0; 256];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.decoder.feed(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        while let Some(command) = self
+            .decoder
+            .decode()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+        {
+            match command {
+                ClientCommand::GetTrack(name) => self.tracks.push(name),
+                ClientCommand::SetRow(row) => self.rows.push(row),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the names of tracks requested by the demo, in the order of the requests.
+    ///
+    /// The position of a name in this slice is the track index used in
+    /// [`set_key`](MockTracker::set_key) and [`delete_key`](MockTracker::delete_key).
+    /// Call [`update`](MockTracker::update) first to receive new requests.
+    pub fn tracks(&self) -> &[String] {
+        &self.tracks
+    }
+
+    /// Get the rows the demo has sent with SET_ROW, in the order they were received.
+    ///
+    /// Call [`update`](MockTracker::update) first to receive new rows.
+    pub fn rows(&self) -> &[u32] {
+        &self.rows
+    }
+
+    /// Get the index of a track requested by the demo.
+    pub fn track_index(&self, name: &str) -> Option<u32> {
+        self.tracks.iter().position(|t| t == name).map(|i| i as u32)
+    }
+
+    /// Block until the demo has requested a track by name, and return its index.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`update`](MockTracker::update).
+    pub fn wait_for_track(&mut self, name: &str) -> Result<u32> {
+        loop {
+            if let Some(index) = self.track_index(name) {
+                return Ok(index);
+            }
+            self.update()?;
+            std::thread::yield_now();
+        }
+    }
+
+    /// Send a SET_KEY command.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error from writing to the demo.
+    pub fn set_key(&mut self, track: u32, key: Key) -> Result<()> {
+        self.send(ServerCommand::SetKey { track, key })
+    }
+
+    /// Send a DELETE_KEY command.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error from writing to the demo.
+    pub fn delete_key(&mut self, track: u32, row: u32) -> Result<()> {
+        self.send(ServerCommand::DeleteKey { track, row })
+    }
+
+    /// Send a SET_ROW command.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error from writing to the demo.
+    pub fn set_row(&mut self, row: u32) -> Result<()> {
+        self.send(ServerCommand::SetRow(row))
+    }
+
+    /// Send a PAUSE command.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error from writing to the demo.
+    pub fn pause(&mut self, paused: bool) -> Result<()> {
+        self.send(ServerCommand::Pause(paused))
+    }
+
+    /// Send a SAVE_TRACKS command.
+    ///
+    /// # Errors
+    ///
+    /// Any IO error from writing to the demo.
+    pub fn save_tracks(&mut self) -> Result<()> {
+        self.send(ServerCommand::SaveTracks)
+    }
+
+    fn send(&mut self, command: ServerCommand) -> Result<()> {
+        let mut encoded = Vec::new();
+        command.encode(&mut encoded);
+
+        // The stream is nonblocking, so retry until everything has been written
+        let mut buf = encoded.as_slice();
+        while !buf.is_empty() {
+            match self.stream.write(buf) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => buf = &buf[n..],
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::yield_now(),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::client::Event;
+    use crate::interpolation::Interpolation;
+    use crate::test_util::{connect_pair, poll_until};
+
+    #[test]
+    fn test_commands() {
+        let (mut rocket, mut tracker) =
+            connect_pair(|stream| MockTracker::with_transport(stream).unwrap());
+        rocket.get_track_mut("a").unwrap();
+        rocket.get_track_mut("b").unwrap();
+        rocket.set_row(7).unwrap();
+
+        assert_eq!(tracker.wait_for_track("b").unwrap(), 1);
+        tracker.update().unwrap();
+        assert_eq!(tracker.tracks(), ["a", "b"]);
+        assert_eq!(tracker.rows(), [7]);
+
+        tracker
+            .set_key(1, Key::new(0, 2.0, Interpolation::Linear))
+            .unwrap();
+        tracker
+            .set_key(1, Key::new(10, 4.0, Interpolation::Step))
+            .unwrap();
+        tracker.set_row(3).unwrap();
+        poll_until(&mut rocket, |e| matches!(e, Event::SetRow(3)));
+        assert_eq!(rocket.get_track("b").unwrap().get_value(5.), 3.0);
+
+        tracker.delete_key(1, 10).unwrap();
+        tracker.pause(true).unwrap();
+        poll_until(&mut rocket, |e| matches!(e, Event::Pause(true)));
+        assert_eq!(rocket.get_track("b").unwrap().get_value(5.), 2.0);
+
+        tracker.save_tracks().unwrap();
+        poll_until(&mut rocket, |e| matches!(e, Event::SaveTracks));
+    }
+}
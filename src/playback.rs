@@ -0,0 +1,322 @@
+//! This module contains [`Playback`], a controller which advances the row in real time.
+use crate::client::Event;
+use crate::timing::Timing;
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// A monotonic clock which drives a [`Playback`].
+pub trait Clock {
+    /// Get the time elapsed since an arbitrary, fixed point in the past.
+    fn now(&self) -> Duration;
+}
+
+/// A [`Clock`] backed by [`Instant`].
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A [`Clock`] which only advances when told to. Useful for tests and offline rendering.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ManualClock {
+    now: Duration,
+}
+
+impl ManualClock {
+    /// Advance the clock.
+    pub fn advance(&mut self, duration: Duration) {
+        self.now += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now
+    }
+}
+
+/// A playback controller for offline playback, used by a [`RocketPlayer`](crate::RocketPlayer).
+///
+/// The controller owns the current row as an `f64`, so tracks can be sampled between rows.
+/// The row advances according to a [`Timing`] and a [`Clock`] every time
+/// [`update`](Playback::update) is called. Changes are reported with the same [`Event`]s as
+/// the [`RocketClient`](crate::RocketClient) reports, [`Event::SetRow`] when the integer row
+/// changes and [`Event::Pause`] when playback is paused or resumed.
+///
+/// Playback starts paused at row 0, unless constructed [`with_paused`](Playback::with_paused).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rust_rocket::RocketPlayer;
+/// # use rust_rocket::client::Event;
+/// # use rust_rocket::playback::Playback;
+/// # use rust_rocket::timing::Timing;
+/// # let tracks = Vec::new();
+/// let mut playback = Playback::new(Timing::new(120., 8));
+/// playback.set_loop(Some(64. ..128.));
+/// playback.play();
+/// let mut player = RocketPlayer::with_playback(tracks, playback);
+/// loop {
+///     player.update();
+///     while let Some(event) = player.playback_mut().poll_events() {
+///         if let Event::SetRow(row) = event {
+///             println!("Row {}", row);
+///         }
+///     }
+///     let row = player.playback().get_row() as f32;
+///     println!("value: {}", player.get_track("test").unwrap().get_value(row));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Playback<C: Clock = SystemClock> {
+    clock: C,
+    timing: Timing,
+    row: f64,
+    paused: bool,
+    speed: f64,
+    loop_region: Option<Range<f64>>,
+    last_update: Duration,
+    events: VecDeque<Event>,
+}
+
+impl Playback<SystemClock> {
+    /// Construct a new `Playback` driven by the system clock.
+    pub fn new(timing: Timing) -> Self {
+        Self::with_clock(SystemClock::default(), timing)
+    }
+}
+
+impl<C: Clock> Playback<C> {
+    /// Construct a new `Playback` driven by `clock`.
+    pub fn with_clock(clock: C, timing: Timing) -> Self {
+        let last_update = clock.now();
+        Self {
+            clock,
+            timing,
+            row: 0.,
+            paused: true,
+            speed: 1.,
+            loop_region: None,
+            last_update,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Start paused or playing, without reporting an [`Event::Pause`].
+    pub fn with_paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    /// Get the clock.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Get the clock mutably, for example to advance a [`ManualClock`].
+    pub fn clock_mut(&mut self) -> &mut C {
+        &mut self.clock
+    }
+
+    /// Get the timing used to convert between rows and time.
+    pub fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    /// Set the timing used to convert between rows and time.
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+
+    /// Get the current row.
+    pub fn get_row(&self) -> f64 {
+        self.row
+    }
+
+    /// Get the time of the current row, see [`Timing::time_at`].
+    pub fn get_time(&self) -> Duration {
+        self.timing.time_at(self.row)
+    }
+
+    /// Check if playback is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Resume playback.
+    pub fn play(&mut self) {
+        self.set_paused(false);
+    }
+
+    /// Pause playback.
+    pub fn pause(&mut self) {
+        self.set_paused(true);
+    }
+
+    /// Pause or resume playback.
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused != self.paused {
+            self.update();
+            self.paused = paused;
+            self.events.push_back(Event::Pause(paused));
+        }
+    }
+
+    /// Jump to a row.
+    pub fn seek(&mut self, row: f64) {
+        self.last_update = self.clock.now();
+        self.set_row(row);
+    }
+
+    /// Jump to a time, see [`Timing::row_at`].
+    pub fn seek_time(&mut self, time: Duration) {
+        self.seek(self.timing.row_at(time));
+    }
+
+    /// Get the playback speed.
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Set the playback speed. 1 is normal speed, and negative speeds play backwards.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.update();
+        self.speed = speed;
+    }
+
+    /// Get the loop region.
+    pub fn loop_region(&self) -> Option<&Range<f64>> {
+        self.loop_region.as_ref()
+    }
+
+    /// Set or clear the loop region.
+    ///
+    /// When playback reaches the end of the region, it jumps back to the start of the region,
+    /// or the other way around when playing backwards.
+    /// Seeking out of the region is possible, playback only loops after entering the region.
+    pub fn set_loop(&mut self, region: Option<Range<f64>>) {
+        self.loop_region = region.filter(|r| r.start < r.end);
+    }
+
+    /// Advance the row by the time elapsed on the clock since the last update.
+    pub fn update(&mut self) {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_update);
+        self.last_update = now;
+        if self.paused {
+            return;
+        }
+
+        let mut row = self.row + elapsed.as_secs_f64() * self.timing.rows_per_second() * self.speed;
+        if let Some(region) = &self.loop_region {
+            let len = region.end - region.start;
+            let inside = region.contains(&self.row);
+            if inside && row >= region.end {
+                row = region.start + (row - region.start) % len;
+            } else if inside && row < region.start {
+                row = region.end - (region.end - row) % len;
+            }
+        }
+        self.set_row(row);
+    }
+
+    /// Get the next event.
+    ///
+    /// Consecutive row changes are merged, so only the latest [`Event::SetRow`] is reported.
+    pub fn poll_events(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    fn set_row(&mut self, row: f64) {
+        let changed = row.floor() != self.row.floor();
+        self.row = row;
+        if changed {
+            let event = Event::SetRow(row as u32);
+            match self.events.back_mut() {
+                Some(last @ Event::SetRow(_)) => *last = event,
+                _ => self.events.push_back(event),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playback() {
+        // 10 rows per second
+        let mut playback = Playback::with_clock(ManualClock::default(), Timing::new(150., 4));
+        playback.clock_mut().advance(Duration::from_secs(1));
+        playback.update();
+        assert_eq!(playback.get_row(), 0.);
+
+        playback.play();
+        assert!(matches!(playback.poll_events(), Some(Event::Pause(false))));
+        playback.clock_mut().advance(Duration::from_millis(250));
+        playback.update();
+        assert_eq!(playback.get_row(), 2.5);
+        playback.clock_mut().advance(Duration::from_millis(50));
+        playback.update();
+        assert!(matches!(playback.poll_events(), Some(Event::SetRow(3))));
+        assert!(playback.poll_events().is_none());
+
+        playback.set_speed(-2.);
+        playback.clock_mut().advance(Duration::from_millis(100));
+        playback.update();
+        assert_eq!(playback.get_row(), 1.);
+
+        playback.seek(100.);
+        playback.pause();
+        playback.clock_mut().advance(Duration::from_secs(1));
+        playback.update();
+        assert_eq!(playback.get_row(), 100.);
+        assert!(matches!(playback.poll_events(), Some(Event::SetRow(100))));
+        assert!(matches!(playback.poll_events(), Some(Event::Pause(true))));
+    }
+
+    #[test]
+    fn test_loop() {
+        let mut playback = Playback::with_clock(ManualClock::default(), Timing::new(150., 4));
+        playback.set_loop(Some(4. ..8.));
+        playback.seek(2.);
+        playback.play();
+
+        playback.clock_mut().advance(Duration::from_millis(300));
+        playback.update();
+        assert_eq!(playback.get_row(), 5.);
+        playback.clock_mut().advance(Duration::from_millis(350));
+        playback.update();
+        assert_eq!(playback.get_row(), 4.5);
+
+        playback.set_speed(-1.);
+        playback.clock_mut().advance(Duration::from_millis(100));
+        playback.update();
+        assert_eq!(playback.get_row(), 7.5);
+
+        // Jumping over the region doesn't enter it
+        playback.seek(2.);
+        playback.set_speed(1.);
+        playback.clock_mut().advance(Duration::from_millis(800));
+        playback.update();
+        assert_eq!(playback.get_row(), 10.);
+    }
+}
@@ -1,10 +1,24 @@
 //! This module contains a barebones player.
+use crate::playback::{Clock, Playback, SystemClock};
+use crate::timing::Timing;
 use crate::track::Track;
 use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// The `Error` Type for [`RocketPlayer`].
+pub enum Error {
+    #[error("Track {0:?} does not exist")]
+    /// The requested track was not in the tracks the player was constructed from.
+    TrackNotFound(String),
+}
 
 /// A player for tracks dumped by
 /// [`RocketClient::save_tracks`](crate::RocketClient::save_tracks).
 ///
+/// The current row is kept by a [`Playback`], which advances it in real time every time
+/// [`update`](RocketPlayer::update) is called.
+///
 /// # Examples
 ///
 /// ```rust,no_run
@@ -22,24 +36,107 @@ use std::collections::HashMap;
 /// let player = RocketPlayer::new(tracks);
 /// println!("Value at row 123: {}", player.get_track("test").unwrap().get_value(123.));
 /// ```
-pub struct RocketPlayer {
+pub struct RocketPlayer<C: Clock = SystemClock> {
     tracks: HashMap<String, Track>,
+    playback: Playback<C>,
 }
 
 impl RocketPlayer {
     /// Constructs a `RocketPlayer` from `Track`s.
+    ///
+    /// The player starts unpaused at row 0, with [`Timing::default`]. Its row only changes
+    /// when it is set, or when [`update`](RocketPlayer::update) advances it in real time with
+    /// the system clock. Use [`with_playback`](RocketPlayer::with_playback) for another timing
+    /// or clock.
     pub fn new(tracks: Vec<Track>) -> Self {
+        Self::with_playback(tracks, Playback::new(Timing::default()).with_paused(false))
+    }
+}
+
+impl<C: Clock> RocketPlayer<C> {
+    /// Constructs a `RocketPlayer` from `Track`s, driven by `playback`.
+    pub fn with_playback(tracks: Vec<Track>, playback: Playback<C>) -> Self {
         // Convert to a HashMap for perf (not benchmarked)
         let mut tracks_map = HashMap::with_capacity(tracks.len());
         for track in tracks {
             tracks_map.insert(track.get_name().to_owned(), track);
         }
 
-        Self { tracks: tracks_map }
+        Self {
+            tracks: tracks_map,
+            playback,
+        }
     }
 
     /// Get track by name.
     pub fn get_track(&self, name: &str) -> Option<&Track> {
         self.tracks.get(name)
     }
+
+    /// Get the playback controller.
+    pub fn playback(&self) -> &Playback<C> {
+        &self.playback
+    }
+
+    /// Get the playback controller mutably, for example to set a loop region.
+    pub fn playback_mut(&mut self) -> &mut Playback<C> {
+        &mut self.playback
+    }
+
+    /// Advance the current row, see [`Playback::update`].
+    pub fn update(&mut self) {
+        self.playback.update();
+    }
+
+    /// Get the current row.
+    pub fn get_row(&self) -> u32 {
+        self.playback.get_row() as u32
+    }
+
+    /// Set the current row.
+    pub fn set_row(&mut self, row: u32) {
+        self.playback.seek(f64::from(row));
+    }
+
+    /// Check if the player is paused.
+    pub fn is_paused(&self) -> bool {
+        self.playback.is_paused()
+    }
+
+    /// Pause or unpause the player.
+    pub fn pause(&mut self, paused: bool) {
+        self.playback.set_paused(paused);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::playback::ManualClock;
+    use crate::track::Key;
+    use std::time::Duration;
+
+    #[test]
+    fn test_playback() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(0, 0.0, Interpolation::Linear));
+        track.set_key(Key::new(16, 1.0, Interpolation::Step));
+        // 10 rows per second
+        let playback = Playback::with_clock(ManualClock::default(), Timing::new(150., 4));
+        let mut player = RocketPlayer::with_playback(vec![track], playback);
+        assert!(player.is_paused());
+
+        player.pause(false);
+        player
+            .playback_mut()
+            .clock_mut()
+            .advance(Duration::from_millis(800));
+        player.update();
+        assert_eq!(player.get_row(), 8);
+        assert_eq!(player.get_track("test").unwrap().get_value(8.), 0.5);
+
+        player.set_row(2);
+        assert_eq!(player.playback().get_row(), 2.);
+    }
 }
@@ -0,0 +1,340 @@
+//! This module contains a sans-IO implementation of the Rocket protocol.
+//!
+//! Commands are decoded from and encoded to byte buffers without touching any socket, so the
+//! same implementation can be used by the [`RocketClient`](crate::RocketClient), the
+//! [`RocketServer`](crate::RocketServer) or any other transport.
+//!
+//! A connection starts with the demo sending [`CLIENT_GREETING`] and the tracker responding
+//! with [`SERVER_GREETING`]. After that, the demo sends [`ClientCommand`]s and the tracker sends
+//! [`ServerCommand`]s. All integers and floats are big endian.
+use crate::interpolation::Interpolation;
+use crate::track::Key;
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use std::marker::PhantomData;
+use thiserror::Error;
+
+/// The greeting a demo sends to the tracker after connecting.
+pub const CLIENT_GREETING: &[u8; 19] = b"hello, synctracker!";
+
+/// The greeting a tracker responds with.
+pub const SERVER_GREETING: &[u8; 12] = b"hello, demo!";
+
+const SET_KEY: u8 = 0;
+const DELETE_KEY: u8 = 1;
+const GET_TRACK: u8 = 2;
+const SET_ROW: u8 = 3;
+const PAUSE: u8 = 4;
+const SAVE_TRACKS: u8 = 5;
+
+#[derive(Debug, Error, Copy, Clone, PartialEq, Eq)]
+/// The `ProtocolError` Type. These are the ways a received command can be invalid.
+pub enum ProtocolError {
+    #[error("Unknown command {0}")]
+    /// The command byte is not known.
+    /// The length of an unknown command can't be known, so the connection is most likely
+    /// unusable after this error.
+    UnknownCommand(u8),
+    #[error("Track index {0} is out of bounds")]
+    /// The tracker referred to a track which was never requested.
+    BadTrackIndex(u32),
+    #[error("Malformed payload for command {command}")]
+    /// The payload of a command could not be decoded.
+    MalformedPayload {
+        /// The command byte.
+        command: u8,
+        /// The length of the whole command, which [`Decoder::decode`] skips.
+        len: usize,
+    },
+}
+
+/// A command in either direction of the Rocket protocol.
+pub trait Command: Sized {
+    /// Decode a command from the start of `buf`.
+    ///
+    /// Returns the command and the number of bytes it used, or `None` if `buf` does not yet
+    /// contain a complete command.
+    ///
+    /// # Errors
+    ///
+    /// [`ProtocolError::UnknownCommand`] or [`ProtocolError::MalformedPayload`] if `buf`
+    /// doesn't start with a valid command.
+    fn decode(buf: &[u8]) -> Result<Option<(Self, usize)>, ProtocolError>;
+
+    /// Encode the command, appending it to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A command sent from the tracker to the demo.
+pub enum ServerCommand {
+    /// Insert or update a key. `track` is the index of the track in the order the demo
+    /// requested tracks in.
+    SetKey {
+        /// Index of the track.
+        track: u32,
+        /// The new key.
+        key: Key,
+    },
+    /// Delete the key at `row`.
+    DeleteKey {
+        /// Index of the track.
+        track: u32,
+        /// Row of the deleted key.
+        row: u32,
+    },
+    /// The tracker changes row.
+    SetRow(u32),
+    /// The tracker pauses or unpauses.
+    Pause(bool),
+    /// The tracker asks the demo to save its track data.
+    SaveTracks,
+}
+
+impl Command for ServerCommand {
+    fn decode(buf: &[u8]) -> Result<Option<(Self, usize)>, ProtocolError> {
+        let cmd = match buf.first() {
+            Some(&cmd) => cmd,
+            None => return Ok(None),
+        };
+        let len = 1 + match cmd {
+            SET_KEY => 4 + 4 + 4 + 1,
+            DELETE_KEY => 4 + 4,
+            SET_ROW => 4,
+            PAUSE => 1,
+            SAVE_TRACKS => 0,
+            _ => return Err(ProtocolError::UnknownCommand(cmd)),
+        };
+        if buf.len() < len {
+            return Ok(None);
+        }
+
+        let command = match cmd {
+            SET_KEY => ServerCommand::SetKey {
+                track: BigEndian::read_u32(&buf[1..]),
+                key: Key::new(
+                    BigEndian::read_u32(&buf[5..]),
+                    BigEndian::read_f32(&buf[9..]),
+                    Interpolation::from(buf[13]),
+                ),
+            },
+            DELETE_KEY => ServerCommand::DeleteKey {
+                track: BigEndian::read_u32(&buf[1..]),
+                row: BigEndian::read_u32(&buf[5..]),
+            },
+            SET_ROW => ServerCommand::SetRow(BigEndian::read_u32(&buf[1..])),
+            PAUSE => ServerCommand::Pause(buf[1] == 1),
+            _ => ServerCommand::SaveTracks,
+        };
+
+        Ok(Some((command, len)))
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match *self {
+            ServerCommand::SetKey { track, key } => {
+                buf.push(SET_KEY);
+                buf.write_u32::<BigEndian>(track).unwrap();
+                buf.write_u32::<BigEndian>(key.row).unwrap();
+                buf.write_f32::<BigEndian>(key.value).unwrap();
+                buf.push(key.interpolation as u8);
+            }
+            ServerCommand::DeleteKey { track, row } => {
+                buf.push(DELETE_KEY);
+                buf.write_u32::<BigEndian>(track).unwrap();
+                buf.write_u32::<BigEndian>(row).unwrap();
+            }
+            ServerCommand::SetRow(row) => {
+                buf.push(SET_ROW);
+                buf.write_u32::<BigEndian>(row).unwrap();
+            }
+            ServerCommand::Pause(paused) => buf.extend_from_slice(&[PAUSE, paused as u8]),
+            ServerCommand::SaveTracks => buf.push(SAVE_TRACKS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A command sent from the demo to the tracker.
+pub enum ClientCommand {
+    /// Request all keys of a track by name.
+    GetTrack(String),
+    /// The demo changes row.
+    SetRow(u32),
+}
+
+impl Command for ClientCommand {
+    fn decode(buf: &[u8]) -> Result<Option<(Self, usize)>, ProtocolError> {
+        let cmd = match buf.first() {
+            Some(&cmd) => cmd,
+            None => return Ok(None),
+        };
+        match cmd {
+            GET_TRACK => {
+                if buf.len() < 1 + 4 {
+                    return Ok(None);
+                }
+                let len = 1 + 4 + BigEndian::read_u32(&buf[1..]) as usize;
+                if buf.len() < len {
+                    return Ok(None);
+                }
+                let name = std::str::from_utf8(&buf[5..len])
+                    .map_err(|_| ProtocolError::MalformedPayload { command: cmd, len })?;
+                Ok(Some((ClientCommand::GetTrack(name.to_owned()), len)))
+            }
+            SET_ROW => {
+                if buf.len() < 1 + 4 {
+                    return Ok(None);
+                }
+                let row = BigEndian::read_u32(&buf[1..]);
+                Ok(Some((ClientCommand::SetRow(row), 1 + 4)))
+            }
+            _ => Err(ProtocolError::UnknownCommand(cmd)),
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            ClientCommand::GetTrack(name) => {
+                buf.push(GET_TRACK);
+                buf.write_u32::<BigEndian>(name.len() as u32).unwrap();
+                buf.extend_from_slice(name.as_bytes());
+            }
+            ClientCommand::SetRow(row) => {
+                buf.push(SET_ROW);
+                buf.write_u32::<BigEndian>(*row).unwrap();
+            }
+        }
+    }
+}
+
+/// An incremental decoder for a stream of [`Command`]s.
+///
+/// Received bytes are buffered with [`feed`](Decoder::feed) and complete commands are taken
+/// out with [`decode`](Decoder::decode).
+///
+/// # Examples
+///
+/// ```
+/// # use rust_rocket::protocol::{Decoder, ServerCommand};
+/// let mut decoder = Decoder::<ServerCommand>::new();
+/// decoder.feed(&[3, 0, 0]);
+/// assert_eq!(decoder.decode(), Ok(None));
+/// decoder.feed(&[1, 0, 5]);
+/// assert_eq!(decoder.decode(), Ok(Some(ServerCommand::SetRow(256))));
+/// assert_eq!(decoder.decode(), Ok(Some(ServerCommand::SaveTracks)));
+/// assert_eq!(decoder.decode(), Ok(None));
+/// ```
+#[derive(Debug)]
+pub struct Decoder<C> {
+    buf: Vec<u8>,
+    command: PhantomData<C>,
+}
+
+impl<C: Command> Default for Decoder<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Command> Decoder<C> {
+    /// Construct a new, empty decoder.
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            command: PhantomData,
+        }
+    }
+
+    /// Append received bytes to the decoder's buffer.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Get the number of buffered bytes which are not yet decoded.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Take the next complete command out of the buffer.
+    ///
+    /// Returns `None` if more bytes are needed.
+    ///
+    /// # Errors
+    ///
+    /// See [`Command::decode`]. On error, the unknown command byte or the malformed command is
+    /// discarded, so decoding can continue if the caller chooses to.
+    pub fn decode(&mut self) -> Result<Option<C>, ProtocolError> {
+        match C::decode(&self.buf) {
+            Ok(Some((command, len))) => {
+                self.buf.drain(..len);
+                Ok(Some(command))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                let len = match e {
+                    ProtocolError::MalformedPayload { len, .. } => len,
+                    _ => 1,
+                };
+                self.buf.drain(..len);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<C: Command + PartialEq + std::fmt::Debug>(commands: &[C]) {
+        let mut buf = Vec::new();
+        for command in commands {
+            command.encode(&mut buf);
+        }
+
+        // Feed one byte at a time to exercise incomplete commands
+        let mut decoder = Decoder::<C>::new();
+        let mut decoded = Vec::new();
+        for byte in buf {
+            decoder.feed(&[byte]);
+            if let Some(command) = decoder.decode().unwrap() {
+                decoded.push(command);
+            }
+        }
+        assert_eq!(decoded, commands);
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip(&[
+            ServerCommand::SetKey {
+                track: 3,
+                key: Key::new(10, -1.5, Interpolation::Ramp),
+            },
+            ServerCommand::DeleteKey { track: 1, row: 7 },
+            ServerCommand::SetRow(1234),
+            ServerCommand::Pause(true),
+            ServerCommand::Pause(false),
+            ServerCommand::SaveTracks,
+        ]);
+        roundtrip(&[
+            ClientCommand::GetTrack("cam:pos.x".to_owned()),
+            ClientCommand::GetTrack(String::new()),
+            ClientCommand::SetRow(99),
+        ]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut decoder = Decoder::<ClientCommand>::new();
+        decoder.feed(&[2, 0, 0, 0, 2, 0xff, 0xfe, 9, 3, 0, 0, 0, 1]);
+        assert_eq!(
+            decoder.decode(),
+            Err(ProtocolError::MalformedPayload { command: 2, len: 7 })
+        );
+        assert_eq!(decoder.decode(), Err(ProtocolError::UnknownCommand(9)));
+        assert_eq!(decoder.decode(), Ok(Some(ClientCommand::SetRow(1))));
+    }
+}
@@ -0,0 +1,361 @@
+//! This module contains [`ReconnectingClient`], a client which survives tracker restarts.
+use crate::client::{Event, RocketClient};
+use crate::timing::Timing;
+use crate::track::Track;
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone)]
+/// The `ReconnectEvent` Type. These are the events from a [`ReconnectingClient`].
+pub enum ReconnectEvent {
+    /// An event from the tracker, see [`RocketClient::poll_events`].
+    Event(Event),
+    /// The connection to the tracker was (re)established.
+    Connected,
+    /// The connection to the tracker was lost.
+    Disconnected,
+}
+
+/// A [`RocketClient`] that reconnects when the connection to the tracker is lost.
+///
+/// Instead of failing with [`Error::IOError`](crate::client::Error::IOError), the client
+/// reports [`ReconnectEvent::Disconnected`] and keeps the tracks it had.
+/// While disconnected, [`poll_events`](ReconnectingClient::poll_events) periodically retries
+/// connecting. After reconnecting, it reports [`ReconnectEvent::Connected`] and requests every
+/// known track from the tracker again.
+///
+/// Tracks keep their keys while the tracker sends its state, so values don't jump to 0 after
+/// reconnecting. The keys of a track are replaced when the first key change for it arrives
+/// from the tracker. Tracks which are empty in the tracker keep their old keys.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rust_rocket::ReconnectingClient;
+/// # use rust_rocket::reconnect::ReconnectEvent;
+/// let mut rocket = ReconnectingClient::new();
+/// rocket.get_track_mut("namespace:track");
+/// loop {
+///     while let Some(event) = rocket.poll_events() {
+///         match event {
+///             ReconnectEvent::Disconnected => println!("Tracker disconnected, retrying..."),
+///             // Do something with the various events.
+///             _ => (),
+///         }
+///     }
+///     # break;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ReconnectingClient {
+    host: String,
+    port: u16,
+    client: Option<RocketClient>,
+    /// Tracks while disconnected.
+    tracks: Vec<Track>,
+    retry_interval: Duration,
+    last_attempt: Instant,
+    pending: Option<ReconnectEvent>,
+    key_events: bool,
+    /// Tracks of the current connection which still have the keys of the previous one.
+    stale: Vec<bool>,
+    row: u32,
+    paused: bool,
+    pub(crate) timing: Timing,
+}
+
+impl ReconnectingClient {
+    /// Construct a new ReconnectingClient.
+    ///
+    /// This constructs a new Rocket client and tries to connect to localhost on port 1338.
+    pub fn new() -> Self {
+        Self::connect("localhost", 1338)
+    }
+
+    /// Construct a new ReconnectingClient.
+    ///
+    /// This constructs a new Rocket client and tries to connect to a specified host and port.
+    /// Failing to connect is not an error, the connection will be retried by
+    /// [`poll_events`](ReconnectingClient::poll_events).
+    /// If the connection succeeds, the first event will be [`ReconnectEvent::Connected`].
+    pub fn connect(host: &str, port: u16) -> Self {
+        let mut rocket = Self {
+            host: host.to_owned(),
+            port,
+            client: None,
+            tracks: Vec::new(),
+            retry_interval: Duration::from_secs(1),
+            last_attempt: Instant::now(),
+            pending: None,
+            key_events: false,
+            stale: Vec::new(),
+            row: 0,
+            paused: true,
+            timing: Timing::default(),
+        };
+        rocket.try_connect();
+        rocket
+    }
+
+    /// Set how often to retry connecting while disconnected. The default is one second.
+    ///
+    /// Connecting blocks, so retrying a remote host very often may cause stutter.
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Enable or disable key events, see [`RocketClient::set_key_events`].
+    ///
+    /// Track indices stay the same across reconnections.
+    pub fn set_key_events(&mut self, enabled: bool) {
+        self.key_events = enabled;
+    }
+
+    /// Check if the client is currently connected to a tracker.
+    pub fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Get track by name.
+    ///
+    /// If the track does not yet exist it will be created.
+    /// If the client is disconnected, the track will be requested after reconnecting.
+    pub fn get_track_mut(&mut self, name: &str) -> &mut Track {
+        if let Some(client) = &mut self.client {
+            if client.get_track_mut(name).is_err() {
+                self.disconnect();
+            }
+        }
+
+        match &mut self.client {
+            Some(client) => client.get_track_mut(name).unwrap(),
+            None => {
+                if let Some(i) = self.tracks.iter().position(|t| t.get_name() == name) {
+                    &mut self.tracks[i]
+                } else {
+                    self.tracks.push(Track::new(name));
+                    self.tracks.last_mut().unwrap()
+                }
+            }
+        }
+    }
+
+    /// Get track by name.
+    ///
+    /// You should use [`get_track_mut`](ReconnectingClient::get_track_mut) to create a track.
+    pub fn get_track(&self, name: &str) -> Option<&Track> {
+        match &self.client {
+            Some(client) => client.get_track(name),
+            None => self.tracks.iter().find(|t| t.get_name() == name),
+        }
+    }
+
+    /// Get track by index, see [`RocketClient::get_track_by_index`].
+    pub fn get_track_by_index(&self, index: usize) -> Option<&Track> {
+        match &self.client {
+            Some(client) => client.get_track_by_index(index),
+            None => self.tracks.get(index),
+        }
+    }
+
+    /// Create a clone of the tracks, see [`RocketClient::save_tracks`].
+    pub fn save_tracks(&self) -> Vec<Track> {
+        match &self.client {
+            Some(client) => client.save_tracks(),
+            None => self.tracks.clone(),
+        }
+    }
+
+    /// Get the current row, see [`RocketClient::get_row`].
+    ///
+    /// The row is kept while disconnected.
+    pub fn get_row(&self) -> u32 {
+        self.row
+    }
+
+    /// Check if the tracker is paused, see [`RocketClient::is_paused`].
+    ///
+    /// The client is paused while disconnected.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Send a SetRow message if connected.
+    pub fn set_row(&mut self, row: u32) {
+        self.row = row;
+        if let Some(client) = &mut self.client {
+            if client.set_row(row).is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    /// Poll for new events from the tracker, and reconnect if needed.
+    ///
+    /// See [`RocketClient::poll_events`].
+    pub fn poll_events(&mut self) -> Option<ReconnectEvent> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+
+        loop {
+            let client = match &mut self.client {
+                Some(client) => client,
+                None => {
+                    if self.last_attempt.elapsed() >= self.retry_interval {
+                        self.try_connect();
+                    }
+                    return self.pending.take();
+                }
+            };
+            let event = match client.poll_events() {
+                Ok(event) => event?,
+                Err(_) => {
+                    self.disconnect();
+                    return self.pending.take();
+                }
+            };
+            match event {
+                Event::SetRow(row) => self.row = row,
+                Event::Pause(paused) => self.paused = paused,
+                Event::KeyChanged { track, row } => self.replace_stale(track, Some(row)),
+                Event::KeyDeleted { track, .. } => self.replace_stale(track, None),
+                Event::SaveTracks => (),
+            }
+            // Key events are always enabled on the client, to find out when tracks are stale
+            if self.key_events
+                || !matches!(event, Event::KeyChanged { .. } | Event::KeyDeleted { .. })
+            {
+                return Some(ReconnectEvent::Event(event));
+            }
+        }
+    }
+
+    /// Drop the keys of the previous connection from a track when the tracker first changes
+    /// it, keeping only the key at `row`.
+    fn replace_stale(&mut self, index: usize, row: Option<u32>) {
+        let client = match &mut self.client {
+            Some(client) => client,
+            None => return,
+        };
+        if !self.stale.get(index).copied().unwrap_or(false) {
+            return;
+        }
+        self.stale[index] = false;
+
+        let track = match client.get_track_by_index(index) {
+            Some(track) => track,
+            None => return,
+        };
+        let mut replaced = Track::new(track.get_name());
+        if let Some(key) = row.and_then(|row| track.key_at(row)) {
+            replaced.set_key(*key);
+        }
+        if let Ok(track) = client.get_track_mut(replaced.get_name()) {
+            *track = replaced;
+        }
+    }
+
+    fn try_connect(&mut self) {
+        self.last_attempt = Instant::now();
+
+        let mut client = match RocketClient::connect(&self.host, self.port) {
+            Ok(client) => client,
+            Err(_) => return,
+        };
+        client.set_key_events(true);
+        for track in &self.tracks {
+            match client.get_track_mut(track.get_name()) {
+                Ok(new) => *new = track.clone(),
+                Err(_) => return,
+            }
+        }
+
+        self.stale = vec![true; self.tracks.len()];
+        self.tracks.clear();
+        self.client = Some(client);
+        self.pending = Some(ReconnectEvent::Connected);
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.tracks = client.save_tracks();
+            self.last_attempt = Instant::now();
+            self.pending = Some(ReconnectEvent::Disconnected);
+            self.paused = true;
+        }
+    }
+}
+
+impl Default for ReconnectingClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::mock::MockTracker;
+    use crate::test_util::wait;
+    use crate::track::Key;
+    use std::net::TcpListener;
+
+    fn wait_event(rocket: &mut ReconnectingClient) -> ReconnectEvent {
+        wait(|| rocket.poll_events())
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accept = std::thread::spawn(move || {
+            let tracker = MockTracker::accept(&listener).unwrap();
+            (listener, tracker)
+        });
+
+        let mut rocket =
+            ReconnectingClient::connect("127.0.0.1", port).with_retry_interval(Duration::ZERO);
+        let (listener, mut tracker) = accept.join().unwrap();
+        assert!(matches!(wait_event(&mut rocket), ReconnectEvent::Connected));
+        rocket.get_track_mut("test");
+
+        let track = tracker.wait_for_track("test").unwrap();
+        tracker
+            .set_key(track, Key::new(0, 2.0, Interpolation::Step))
+            .unwrap();
+        tracker.pause(true).unwrap();
+        assert!(matches!(
+            wait_event(&mut rocket),
+            ReconnectEvent::Event(Event::Pause(true))
+        ));
+
+        drop(tracker);
+        assert!(matches!(
+            wait_event(&mut rocket),
+            ReconnectEvent::Disconnected
+        ));
+        assert!(!rocket.is_connected());
+        assert_eq!(rocket.get_track("test").unwrap().get_value(0.), 2.0);
+
+        let accept = std::thread::spawn(move || MockTracker::accept(&listener).unwrap());
+        assert!(matches!(wait_event(&mut rocket), ReconnectEvent::Connected));
+        let mut tracker = accept.join().unwrap();
+        assert_eq!(tracker.wait_for_track("test").unwrap(), 0);
+
+        // The old keys are kept until the tracker sends the track
+        assert_eq!(rocket.get_track("test").unwrap().get_value(0.), 2.0);
+        tracker
+            .set_key(0, Key::new(4, 3.0, Interpolation::Step))
+            .unwrap();
+        tracker.pause(false).unwrap();
+        assert!(matches!(
+            wait_event(&mut rocket),
+            ReconnectEvent::Event(Event::Pause(false))
+        ));
+        assert_eq!(rocket.get_track("test").unwrap().keys().len(), 1);
+        assert_eq!(rocket.get_track("test").unwrap().get_value(0.), 3.0);
+    }
+}
@@ -0,0 +1,586 @@
+//! This module contains the tracker side of the protocol, including the [`RocketServer`] type.
+use crate::interpolation::Encoding;
+use crate::protocol::{ClientCommand, Command, Decoder, ServerCommand};
+use crate::protocol::{CLIENT_GREETING, SERVER_GREETING};
+use crate::track::*;
+use crate::transport::Transport;
+
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// The `Error` Type for [`RocketServer`].
+pub enum Error {
+    #[error("Failed to listen for Rocket connections")]
+    /// Failure to bind the listening socket.
+    Bind(#[source] std::io::Error),
+    #[error("Failed to accept a Rocket connection")]
+    /// Failure to accept a pending connection.
+    Accept(#[source] std::io::Error),
+    #[error("Handshake with the Rocket demo failed")]
+    /// Failure to transmit or receive greetings with the demo
+    Handshake(#[source] std::io::Error),
+    #[error("The Rocket demo greeting {0:?} wasn't correct")]
+    /// Handshake was performed but the the received greeting wasn't correct
+    HandshakeGreetingMismatch([u8; 19]),
+    #[error("Cannot set Rocket's connection to nonblocking mode")]
+    /// Error from [`Transport::set_nonblocking`]
+    SetNonblocking(#[source] std::io::Error),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Identifies a demo connected to a [`RocketServer`].
+pub struct ClientId(u64);
+
+#[derive(Debug, Clone)]
+/// The `ServerEvent` Type. These are the various events from connected demos.
+pub enum ServerEvent {
+    /// A demo requested a track. The server has already sent it all keys of the track.
+    GetTrack {
+        /// The demo that requested the track.
+        client: ClientId,
+        /// Name of the track.
+        name: String,
+    },
+    /// A demo changed its row.
+    SetRow {
+        /// The demo that changed its row.
+        client: ClientId,
+        /// The new row.
+        row: u32,
+    },
+    /// A demo disconnected, or was disconnected because of an IO or protocol error.
+    Disconnected(ClientId),
+}
+
+#[derive(Debug)]
+struct Client<S> {
+    id: ClientId,
+    stream: S,
+    decoder: Decoder<ClientCommand>,
+    output: Vec<u8>,
+    /// Server track indices, indexed by the demo's own track indices.
+    tracks: Vec<usize>,
+}
+
+impl<S: Transport> Client<S> {
+    /// Write as much of the output buffer as the transport accepts without blocking.
+    fn flush(&mut self) -> std::io::Result<()> {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.output.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Read everything the transport has available without blocking.
+    fn receive(&mut self) -> std::io::Result<()> {
+        let mut chunk = [0; 256];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.decoder.feed(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn queue(&mut self, command: ServerCommand) {
+        command.encode(&mut self.output);
+    }
+
+    fn client_track(&self, server_track: usize) -> Option<u32> {
+        self.tracks
+            .iter()
+            .position(|&t| t == server_track)
+            .map(|i| i as u32)
+    }
+}
+
+/// Replace the interpolation of `key` with one supported by `encoding`.
+fn encode_key(mut key: Key, encoding: Encoding) -> Key {
+    key.interpolation = key.interpolation.encode(encoding);
+    key
+}
+
+#[derive(Debug)]
+/// The `RocketServer` type. This is the tracker (editor) side of the Rocket protocol.
+///
+/// The server owns the authoritative [`Track`] state.
+/// Tracks requested by demos are created on demand, and demos receive all existing keys of a
+/// track when they request it.
+/// Edits made through the server are broadcast to every demo that has requested the track.
+///
+/// The server is generic over its [`Transport`], which is a [`TcpStream`] by default.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rust_rocket::server::{RocketServer, ServerEvent};
+/// # use rust_rocket::interpolation::Interpolation;
+/// # use rust_rocket::track::Key;
+/// let mut server = RocketServer::bind("localhost:1338").unwrap();
+/// loop {
+///     while server.accept().unwrap().is_some() {}
+///     while let Some(event) = server.poll_events() {
+///         if let ServerEvent::GetTrack { name, .. } = event {
+///             server.set_key(&name, Key::new(0, 1.0, Interpolation::Linear));
+///         }
+///     }
+/// }
+/// ```
+pub struct RocketServer<S: Transport = TcpStream> {
+    listener: Option<TcpListener>,
+    tracks: Vec<Track>,
+    clients: Vec<Client<S>>,
+    /// Position of the client to poll first, so that a busy demo doesn't starve the others.
+    next_client: usize,
+    next_id: u64,
+    disconnected: Vec<ClientId>,
+    interpolation_encoding: Encoding,
+    handshake_timeout: Duration,
+}
+
+impl RocketServer<TcpStream> {
+    /// Construct a new RocketServer listening for demos on `addr`.
+    ///
+    /// Rocket demos connect to port 1338 by default.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Bind`] if the socket cannot be bound, or [`Error::SetNonblocking`].
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).map_err(Error::Bind)?;
+        listener
+            .set_nonblocking(true)
+            .map_err(Error::SetNonblocking)?;
+
+        let mut server = Self::new();
+        server.listener = Some(listener);
+        Ok(server)
+    }
+
+    /// Get the address the server is listening on.
+    ///
+    /// Returns `None` if the server was not constructed with [`bind`](RocketServer::bind).
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.as_ref()?.local_addr().ok()
+    }
+
+    /// Set how long [`accept`](RocketServer::accept) waits for a demo's greeting. The default
+    /// is one second.
+    ///
+    /// # Panics
+    ///
+    /// If `timeout` is zero.
+    pub fn set_handshake_timeout(&mut self, timeout: Duration) {
+        assert!(!timeout.is_zero(), "timeout must not be zero");
+        self.handshake_timeout = timeout;
+    }
+
+    /// Accept a pending demo connection, if any.
+    ///
+    /// This does not block waiting for connections.
+    /// You should call this fairly often your main loop, as long as it returns `Some`.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Accept`] if accepting fails, or any error from
+    /// [`add_client`](RocketServer::add_client). A demo which doesn't complete the handshake
+    /// within the [handshake timeout](RocketServer::set_handshake_timeout) is dropped with
+    /// [`Error::Handshake`].
+    pub fn accept(&mut self) -> Result<Option<ClientId>, Error> {
+        let listener = match &self.listener {
+            Some(listener) => listener,
+            None => return Ok(None),
+        };
+
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // The handshake is done in blocking mode, with a timeout so that a silent demo
+                // can't stall the server
+                stream
+                    .set_nonblocking(false)
+                    .map_err(Error::SetNonblocking)?;
+                stream
+                    .set_read_timeout(Some(self.handshake_timeout))
+                    .and_then(|()| stream.set_write_timeout(Some(self.handshake_timeout)))
+                    .map_err(Error::Handshake)?;
+                self.add_client(stream).map(Some)
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(Error::Accept(e)),
+        }
+    }
+}
+
+impl<S: Transport> Default for RocketServer<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Transport> RocketServer<S> {
+    /// Construct a new RocketServer without a listening socket and without tracks.
+    ///
+    /// Demos can be connected with [`add_client`](RocketServer::add_client).
+    pub fn new() -> Self {
+        Self {
+            listener: None,
+            tracks: Vec::new(),
+            clients: Vec::new(),
+            next_client: 0,
+            next_id: 0,
+            disconnected: Vec::new(),
+            interpolation_encoding: Encoding::Basic,
+            handshake_timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Replace the server's tracks, for example with tracks loaded from a file.
+    ///
+    /// Connected demos will not be notified, so this should be called before they connect.
+    pub fn with_tracks(mut self, tracks: Vec<Track>) -> Self {
+        self.tracks = tracks;
+        self
+    }
+
+    /// Set which interpolation types demos support. The default is [`Encoding::Basic`], so
+    /// extended types are sent as their [`fallback`](crate::interpolation::Interpolation::fallback)
+    /// like GNU Rocket demos expect. The server's own tracks keep the original types.
+    pub fn set_interpolation_encoding(&mut self, encoding: Encoding) {
+        self.interpolation_encoding = encoding;
+    }
+
+    /// Connect a demo over an already connected [`Transport`].
+    ///
+    /// This performs the handshake over `stream` and switches it to nonblocking mode.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Handshake`] if the handshake fails, or [`Error::SetNonblocking`] if the
+    /// transport cannot be made nonblocking.
+    pub fn add_client(&mut self, mut stream: S) -> Result<ClientId, Error> {
+        let mut buf = [0; 19];
+        stream.read_exact(&mut buf).map_err(Error::Handshake)?;
+        if &buf != CLIENT_GREETING {
+            return Err(Error::HandshakeGreetingMismatch(buf));
+        }
+        stream
+            .write_all(SERVER_GREETING)
+            .map_err(Error::Handshake)?;
+        stream
+            .set_nonblocking(true)
+            .map_err(Error::SetNonblocking)?;
+
+        let id = ClientId(self.next_id);
+        self.next_id += 1;
+        self.clients.push(Client {
+            id,
+            stream,
+            decoder: Decoder::new(),
+            output: Vec::new(),
+            tracks: Vec::new(),
+        });
+
+        Ok(id)
+    }
+
+    /// Get the ids of all connected demos.
+    pub fn clients(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.clients.iter().map(|c| c.id)
+    }
+
+    /// Get all tracks.
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Get track by name.
+    pub fn get_track(&self, name: &str) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.get_name() == name)
+    }
+
+    /// Create a clone of the tracks, see
+    /// [`RocketClient::save_tracks`](crate::RocketClient::save_tracks).
+    pub fn save_tracks(&self) -> Vec<Track> {
+        self.tracks.clone()
+    }
+
+    /// Insert or update a key on a track, and send it to demos which use the track.
+    ///
+    /// If the track does not yet exist it will be created.
+    pub fn set_key(&mut self, track: &str, key: Key) {
+        let index = self.track_index_or_create(track);
+        self.tracks[index].set_key(key);
+        let key = encode_key(key, self.interpolation_encoding);
+        for client in &mut self.clients {
+            if let Some(client_track) = client.client_track(index) {
+                client.queue(ServerCommand::SetKey {
+                    track: client_track,
+                    key,
+                });
+            }
+        }
+        self.flush();
+    }
+
+    /// Delete a key from a track, and send the deletion to demos which use the track.
+    ///
+    /// If the track or key does not exist this will do nothing.
+    pub fn delete_key(&mut self, track: &str, row: u32) {
+        let index = match self.tracks.iter().position(|t| t.get_name() == track) {
+            Some(index) => index,
+            None => return,
+        };
+        self.tracks[index].delete_key(row);
+        for client in &mut self.clients {
+            if let Some(client_track) = client.client_track(index) {
+                client.queue(ServerCommand::DeleteKey {
+                    track: client_track,
+                    row,
+                });
+            }
+        }
+        self.flush();
+    }
+
+    /// Send a SetRow message to all demos.
+    pub fn set_row(&mut self, row: u32) {
+        self.broadcast(ServerCommand::SetRow(row));
+    }
+
+    /// Send a Pause message to all demos.
+    pub fn pause(&mut self, paused: bool) {
+        self.broadcast(ServerCommand::Pause(paused));
+    }
+
+    /// Ask all demos to save their tracks.
+    pub fn request_save(&mut self) {
+        self.broadcast(ServerCommand::SaveTracks);
+    }
+
+    /// Poll for new events from connected demos.
+    ///
+    /// Demos whose connection fails are dropped and reported as
+    /// [`ServerEvent::Disconnected`].
+    /// It is recommended to keep calling this as long as your receive `Some(ServerEvent)`.
+    ///
+    /// Demos are polled in turns, starting after the demo which reported the last event.
+    pub fn poll_events(&mut self) -> Option<ServerEvent> {
+        loop {
+            if let Some(id) = self.disconnected.pop() {
+                return Some(ServerEvent::Disconnected(id));
+            }
+
+            let mut event = None;
+            let count = self.clients.len();
+            for n in 0..count {
+                let i = (self.next_client + n) % count;
+                let result = self.clients[i].receive().and_then(|_| self.process(i));
+                match result {
+                    Ok(Some(e)) => {
+                        event = Some(e);
+                        self.next_client = i + 1;
+                        break;
+                    }
+                    Ok(None) => (),
+                    Err(_) => self.disconnected.push(self.clients[i].id),
+                }
+            }
+            self.flush();
+
+            if event.is_some() || self.disconnected.is_empty() {
+                return event;
+            }
+        }
+    }
+
+    /// Process one complete command received from client `i`.
+    fn process(&mut self, i: usize) -> std::io::Result<Option<ServerEvent>> {
+        let client = &mut self.clients[i];
+        let command = client
+            .decoder
+            .decode()
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+
+        match command {
+            Some(ClientCommand::GetTrack(name)) => {
+                let index = self.track_index_or_create(&name);
+                let client = &mut self.clients[i];
+                let client_track = client.tracks.len() as u32;
+                client.tracks.push(index);
+                for &key in &self.tracks[index].keys {
+                    client.queue(ServerCommand::SetKey {
+                        track: client_track,
+                        key: encode_key(key, self.interpolation_encoding),
+                    });
+                }
+
+                Ok(Some(ServerEvent::GetTrack {
+                    client: client.id,
+                    name,
+                }))
+            }
+            Some(ClientCommand::SetRow(row)) => Ok(Some(ServerEvent::SetRow {
+                client: client.id,
+                row,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    fn track_index_or_create(&mut self, name: &str) -> usize {
+        match self.tracks.iter().position(|t| t.get_name() == name) {
+            Some(index) => index,
+            None => {
+                self.tracks.push(Track::new(name));
+                self.tracks.len() - 1
+            }
+        }
+    }
+
+    fn broadcast(&mut self, command: ServerCommand) {
+        for client in &mut self.clients {
+            client.queue(command);
+        }
+        self.flush();
+    }
+
+    /// Flush all output buffers and drop clients whose connection failed.
+    fn flush(&mut self) {
+        let disconnected = &mut self.disconnected;
+        self.clients.retain_mut(|client| match client.flush() {
+            Ok(()) => !disconnected.contains(&client.id),
+            Err(_) => {
+                disconnected.push(client.id);
+                false
+            }
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::client::{Event, RocketClient};
+    use crate::interpolation::Interpolation;
+    use crate::test_util::{connect_pair, poll_until, wait};
+    use std::os::unix::net::UnixStream;
+
+    fn connect(server: &mut RocketServer<UnixStream>) -> (RocketClient<UnixStream>, ClientId) {
+        connect_pair(|stream| server.add_client(stream).unwrap())
+    }
+
+    fn wait_event(server: &mut RocketServer<UnixStream>) -> ServerEvent {
+        wait(|| server.poll_events())
+    }
+
+    #[test]
+    fn test_get_track_and_broadcast() {
+        let mut track = Track::new("b");
+        track.set_key(Key::new(0, 1.0, Interpolation::Linear));
+        track.set_key(Key::new(10, 3.0, Interpolation::Step));
+        let mut server = RocketServer::new().with_tracks(vec![track]);
+
+        let (mut first, first_id) = connect(&mut server);
+        let (mut second, second_id) = connect(&mut server);
+
+        // Request tracks in different orders so that track indices differ between demos
+        first.get_track_mut("a").unwrap();
+        first.get_track_mut("b").unwrap();
+        second.get_track_mut("b").unwrap();
+        for _ in 0..3 {
+            assert!(matches!(
+                wait_event(&mut server),
+                ServerEvent::GetTrack { .. }
+            ));
+        }
+        assert_eq!(server.tracks().len(), 2);
+
+        server.set_key("b", Key::new(20, 5.0, Interpolation::Step));
+        server.set_key("a", Key::new(0, 0.0, Interpolation::EaseIn));
+        server.set_row(1);
+        poll_until(&mut first, |e| matches!(e, Event::SetRow(1)));
+        poll_until(&mut second, |e| matches!(e, Event::SetRow(1)));
+        for rocket in [&first, &second] {
+            let track = rocket.get_track("b").unwrap();
+            assert_eq!(track.get_value(5.), 2.0);
+            assert_eq!(track.get_value(20.), 5.0);
+        }
+        assert_eq!(first.get_track("a").unwrap().get_value(20.), 0.0);
+        // Extended interpolation types are sent as basic types by default
+        assert_eq!(
+            first.get_track("a").unwrap().keys[0].interpolation,
+            Interpolation::Ramp
+        );
+        assert_eq!(
+            server.tracks()[1].keys[0].interpolation,
+            Interpolation::EaseIn
+        );
+
+        server.delete_key("b", 20);
+        server.pause(true);
+        poll_until(&mut second, |e| matches!(e, Event::Pause(true)));
+        assert_eq!(second.get_track("b").unwrap().get_value(20.), 3.0);
+
+        second.set_row(8).unwrap();
+        match wait_event(&mut server) {
+            ServerEvent::SetRow { client, row } => {
+                assert_eq!(client, second_id);
+                assert_eq!(row, 8);
+            }
+            e => panic!("unexpected event {:?}", e),
+        }
+
+        drop(first);
+        match wait_event(&mut server) {
+            ServerEvent::Disconnected(client) => assert_eq!(client, first_id),
+            e => panic!("unexpected event {:?}", e),
+        }
+        assert_eq!(server.clients().collect::<Vec<_>>(), [second_id]);
+    }
+
+    #[test]
+    fn test_poll_in_turns() {
+        let mut server = RocketServer::new();
+        let (mut first, first_id) = connect(&mut server);
+        let (mut second, second_id) = connect(&mut server);
+
+        first.set_row(1).unwrap();
+        first.set_row(2).unwrap();
+        second.set_row(3).unwrap();
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            match wait_event(&mut server) {
+                ServerEvent::SetRow { client, row } => events.push((client, row)),
+                e => panic!("unexpected event {:?}", e),
+            }
+        }
+        assert_eq!(events, [(first_id, 1), (second_id, 3), (first_id, 2)]);
+    }
+
+    #[test]
+    fn test_handshake_timeout() {
+        let mut server = RocketServer::bind("127.0.0.1:0").unwrap();
+        server.set_handshake_timeout(Duration::from_millis(50));
+        let _silent = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        loop {
+            match server.accept() {
+                Ok(None) => std::thread::yield_now(),
+                Err(Error::Handshake(_)) => break,
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+        assert_eq!(server.clients().count(), 0);
+    }
+}
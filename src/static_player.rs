@@ -0,0 +1,146 @@
+//! This module contains [`StaticPlayer`], a player for keys in static arrays.
+//!
+//! Unlike [`RocketPlayer`](crate::RocketPlayer), it works without the `std` feature and never
+//! allocates, so it can be used on embedded targets and in size-coded demos.
+use crate::timing::Timing;
+use crate::track::{self, Key};
+use core::time::Duration;
+
+/// A table of tracks, as `(name, keys)` pairs sorted by name.
+///
+/// The keys of each track must be sorted by row without duplicate rows.
+/// [`codegen`](crate::codegen) generates such a table as `TRACKS`.
+pub type Tracks = &'static [(&'static str, &'static [Key])];
+
+/// A player for tracks in a static table.
+///
+/// # Examples
+///
+/// ```
+/// # use rust_rocket::interpolation::Interpolation;
+/// # use rust_rocket::track::Key;
+/// # use rust_rocket::StaticPlayer;
+/// static FADE: &[Key] = &[
+///     Key::new(0, 0.0, Interpolation::Linear),
+///     Key::new(16, 1.0, Interpolation::Step),
+/// ];
+/// static TRACKS: &[(&str, &[Key])] = &[("fade", FADE)];
+///
+/// let mut player = StaticPlayer::new(TRACKS);
+/// player.set_row(4);
+/// assert_eq!(player.get_value("fade", player.get_row() as f32), Some(0.25));
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticPlayer {
+    tracks: Tracks,
+    row: u32,
+    paused: bool,
+    timing: Timing,
+}
+
+impl StaticPlayer {
+    /// Constructs a `StaticPlayer` from a table of tracks, see [`Tracks`].
+    ///
+    /// Tracks are looked up with a binary search, so the table must be sorted by name. This is
+    /// only checked in debug builds.
+    pub fn new(tracks: Tracks) -> Self {
+        debug_assert!(
+            tracks.windows(2).all(|w| w[0].0 < w[1].0),
+            "tracks must be sorted by name"
+        );
+        Self {
+            tracks,
+            row: 0,
+            paused: false,
+            timing: Timing::default(),
+        }
+    }
+
+    /// Get the keys of a track by name.
+    pub fn get_track(&self, name: &str) -> Option<&'static [Key]> {
+        self.tracks
+            .binary_search_by(|(n, _)| (*n).cmp(name))
+            .ok()
+            .map(|pos| self.tracks[pos].1)
+    }
+
+    /// Get the value of a track at a row, see [`track::sample`].
+    pub fn get_value(&self, name: &str, row: f32) -> Option<f32> {
+        self.get_track(name).map(|keys| track::sample(keys, row))
+    }
+
+    /// Get the current row.
+    pub fn get_row(&self) -> u32 {
+        self.row
+    }
+
+    /// Set the current row.
+    pub fn set_row(&mut self, row: u32) {
+        self.row = row;
+    }
+
+    /// Check if the player is paused. Players start unpaused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pause or unpause the player.
+    pub fn pause(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Get the timing used to convert between rows and time.
+    pub fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    /// Set the timing used to convert between rows and time. The default is
+    /// [`Timing::default`].
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+
+    /// Get the value of a track at a time instead of a row.
+    pub fn get_value_at_time(&self, name: &str, time: Duration) -> Option<f32> {
+        self.get_value(name, self.timing.row_at(time) as f32)
+    }
+
+    /// Get the start time of the current row.
+    pub fn get_time(&self) -> Duration {
+        self.timing.time_at(f64::from(self.row))
+    }
+
+    /// Set the current row to the row at `time`.
+    pub fn set_time(&mut self, time: Duration) {
+        self.row = self.timing.row_at(time) as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+
+    static A: &[Key] = &[
+        Key::new(0, 0.0, Interpolation::Linear),
+        Key::new(16, 1.0, Interpolation::Step),
+    ];
+    static B: &[Key] = &[Key::new(0, 2.0, Interpolation::Step)];
+    static TRACKS: Tracks = &[("a", A), ("b", B), ("c", &[])];
+
+    #[test]
+    fn test_lookup() {
+        let mut player = StaticPlayer::new(TRACKS);
+        player.set_timing(Timing::new(60., 4));
+
+        assert_eq!(player.get_value("a", 8.), Some(0.5));
+        assert_eq!(player.get_value("b", 8.), Some(2.));
+        assert_eq!(player.get_value("c", 8.), Some(0.));
+        assert_eq!(player.get_track("d"), None);
+        assert_eq!(player.get_track("0"), None);
+        assert_eq!(
+            player.get_value_at_time("a", Duration::from_secs(2)),
+            Some(0.5)
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! This module contains the [`SyncSource`] trait, which lets the same demo code run with a
+//! [`RocketClient`] while editing and with a [`RocketPlayer`] in release builds.
+use crate::client::{self, Event, RocketClient};
+use crate::playback::Clock;
+use crate::player::{self, RocketPlayer};
+use crate::reconnect::{ReconnectEvent, ReconnectingClient};
+use crate::timing::Timing;
+use crate::track::Track;
+use crate::transport::Transport;
+use std::time::Duration;
+
+/// A source of sync tracks and the current row.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rust_rocket::{RocketClient, RocketPlayer, SyncSource};
+/// fn frame<S: SyncSource>(sync: &mut S) -> Result<(), S::Error> {
+///     while sync.poll_events()?.is_some() {}
+///     let row = sync.get_row() as f32;
+///     println!("value: {}", sync.get_value("test", row)?);
+///     if !sync.is_paused() {
+///         sync.set_row(sync.get_row() + 1)?;
+///     }
+///     Ok(())
+/// }
+///
+/// # let edit = true;
+/// if edit {
+///     frame(&mut RocketClient::new().unwrap()).unwrap();
+/// } else {
+///     # let tracks = Vec::new();
+///     frame(&mut RocketPlayer::new(tracks)).unwrap();
+/// }
+/// ```
+pub trait SyncSource {
+    /// The error type of the source.
+    type Error;
+
+    /// Get track by name.
+    ///
+    /// Clients create and request tracks which do not yet exist.
+    fn get_track(&mut self, name: &str) -> Result<&Track, Self::Error>;
+
+    /// Get the value of a track at a row, see [`Track::get_value`].
+    fn get_value(&mut self, name: &str, row: f32) -> Result<f32, Self::Error> {
+        Ok(self.get_track(name)?.get_value(row))
+    }
+
+    /// Get the current row.
+    fn get_row(&self) -> u32;
+
+    /// Set the current row.
+    fn set_row(&mut self, row: u32) -> Result<(), Self::Error>;
+
+    /// Check if playback is paused.
+    fn is_paused(&self) -> bool;
+
+    /// Poll for new events from the tracker, or from the playback of a [`RocketPlayer`].
+    fn poll_events(&mut self) -> Result<Option<Event>, Self::Error>;
+
+    /// Get the timing used to convert between rows and time.
+    fn timing(&self) -> Timing;
+
+    /// Set the timing used to convert between rows and time. The default is
+    /// [`Timing::default`].
+    fn set_timing(&mut self, timing: Timing);
+
+    /// Get the value of a track at a time instead of a row.
+    fn get_value_at_time(&mut self, name: &str, time: Duration) -> Result<f32, Self::Error> {
+        let row = self.timing().row_at(time) as f32;
+        self.get_value(name, row)
+    }
+
+    /// Get the start time of the current row, see [`get_row`](SyncSource::get_row).
+    fn get_time(&self) -> Duration {
+        self.timing().time_at(f64::from(self.get_row()))
+    }
+
+    /// Set the current row to the row at `time`.
+    fn set_time(&mut self, time: Duration) -> Result<(), Self::Error> {
+        let row = self.timing().row_at(time) as u32;
+        self.set_row(row)
+    }
+}
+
+impl<S: Transport> SyncSource for RocketClient<S> {
+    type Error = client::Error;
+
+    fn get_track(&mut self, name: &str) -> Result<&Track, Self::Error> {
+        self.get_track_mut(name).map(|track| &*track)
+    }
+
+    fn get_row(&self) -> u32 {
+        RocketClient::get_row(self)
+    }
+
+    fn set_row(&mut self, row: u32) -> Result<(), Self::Error> {
+        RocketClient::set_row(self, row)
+    }
+
+    fn is_paused(&self) -> bool {
+        RocketClient::is_paused(self)
+    }
+
+    fn poll_events(&mut self) -> Result<Option<Event>, Self::Error> {
+        RocketClient::poll_events(self)
+    }
+
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+}
+
+impl SyncSource for ReconnectingClient {
+    type Error = std::convert::Infallible;
+
+    fn get_track(&mut self, name: &str) -> Result<&Track, Self::Error> {
+        Ok(self.get_track_mut(name))
+    }
+
+    fn get_row(&self) -> u32 {
+        ReconnectingClient::get_row(self)
+    }
+
+    fn set_row(&mut self, row: u32) -> Result<(), Self::Error> {
+        ReconnectingClient::set_row(self, row);
+        Ok(())
+    }
+
+    fn is_paused(&self) -> bool {
+        ReconnectingClient::is_paused(self)
+    }
+
+    /// Connection events are skipped.
+    fn poll_events(&mut self) -> Result<Option<Event>, Self::Error> {
+        loop {
+            match ReconnectingClient::poll_events(self) {
+                Some(ReconnectEvent::Event(event)) => return Ok(Some(event)),
+                Some(_) => (),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+}
+
+impl<C: Clock> SyncSource for RocketPlayer<C> {
+    type Error = player::Error;
+
+    fn get_track(&mut self, name: &str) -> Result<&Track, Self::Error> {
+        RocketPlayer::get_track(self, name).ok_or_else(|| player::Error::TrackNotFound(name.into()))
+    }
+
+    fn get_row(&self) -> u32 {
+        RocketPlayer::get_row(self)
+    }
+
+    fn set_row(&mut self, row: u32) -> Result<(), Self::Error> {
+        RocketPlayer::set_row(self, row);
+        Ok(())
+    }
+
+    fn is_paused(&self) -> bool {
+        RocketPlayer::is_paused(self)
+    }
+
+    /// Reports the events of the player's [`Playback`](crate::playback::Playback).
+    fn poll_events(&mut self) -> Result<Option<Event>, Self::Error> {
+        Ok(self.playback_mut().poll_events())
+    }
+
+    fn timing(&self) -> Timing {
+        *self.playback().timing()
+    }
+
+    fn set_timing(&mut self, timing: Timing) {
+        self.playback_mut().set_timing(timing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::track::Key;
+
+    fn advance<S: SyncSource>(sync: &mut S) -> Result<f32, S::Error> {
+        if !sync.is_paused() {
+            sync.set_row(sync.get_row() + 1)?;
+        }
+        sync.get_value("test", sync.get_row() as f32)
+    }
+
+    #[test]
+    fn test_player() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(0, 0.0, Interpolation::Linear));
+        track.set_key(Key::new(4, 4.0, Interpolation::Step));
+        let mut player = RocketPlayer::new(vec![track]);
+
+        assert_eq!(advance(&mut player).unwrap(), 1.0);
+        assert_eq!(advance(&mut player).unwrap(), 2.0);
+        player.pause(true);
+        assert_eq!(advance(&mut player).unwrap(), 2.0);
+        assert!(matches!(
+            player.get_value("typo", 0.),
+            Err(player::Error::TrackNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_timing() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(0, 0.0, Interpolation::Linear));
+        track.set_key(Key::new(16, 1.0, Interpolation::Step));
+        let mut player = RocketPlayer::new(vec![track]);
+        player.set_timing(Timing::new(60., 4));
+
+        assert_eq!(
+            player
+                .get_value_at_time("test", Duration::from_secs(2))
+                .unwrap(),
+            0.5
+        );
+        assert!(player.get_value_at_time("missing", Duration::ZERO).is_err());
+
+        player.set_time(Duration::from_millis(2600)).unwrap();
+        assert_eq!(player.get_row(), 10);
+        assert_eq!(player.get_time(), Duration::from_millis(2500));
+    }
+}
@@ -0,0 +1,35 @@
+//! Helpers shared by the tests of several modules.
+use crate::client::{Event, RocketClient};
+use crate::transport::Transport;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+/// Call `poll` until it returns `Some`.
+pub fn wait<T>(mut poll: impl FnMut() -> Option<T>) -> T {
+    loop {
+        if let Some(value) = poll() {
+            return value;
+        }
+        std::thread::yield_now();
+    }
+}
+
+/// Poll `rocket` until it reports an event for which `f` returns true.
+pub fn poll_until<S: Transport>(rocket: &mut RocketClient<S>, f: impl Fn(Event) -> bool) {
+    wait(|| rocket.poll_events().unwrap().filter(|&e| f(e)));
+}
+
+/// Connect a client to the tracker side returned by `tracker`, which is run on another thread
+/// during the handshake.
+#[cfg(unix)]
+pub fn connect_pair<T: Send>(
+    tracker: impl FnOnce(UnixStream) -> T + Send,
+) -> (RocketClient<UnixStream>, T) {
+    let (client, server) = UnixStream::pair().unwrap();
+    std::thread::scope(|scope| {
+        let tracker = scope.spawn(move || tracker(server));
+        let rocket = RocketClient::with_transport(client).unwrap();
+        (rocket, tracker.join().unwrap())
+    })
+}
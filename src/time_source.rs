@@ -0,0 +1,154 @@
+//! This module contains the [`TimeSource`] trait, which lets a demo follow the playback
+//! position of its music.
+//!
+//! Demos should sync to the music rather than to the frame rate. Implement [`TimeSource`] for
+//! your audio backend, and use [`poll_events`] instead of [`SyncSource::poll_events`] to keep
+//! the music and the sync source in step: the tracker seeks and pauses the music while editing,
+//! and the music advances the row.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # use rust_rocket::{RocketClient, SyncSource};
+//! # use rust_rocket::playback::Playback;
+//! # use rust_rocket::time_source::{self, TimeSource};
+//! # use rust_rocket::timing::Timing;
+//! let mut rocket = RocketClient::new().unwrap();
+//! rocket.set_timing(Timing::new(140., 8));
+//! // Replace with your audio backend
+//! let mut music = Playback::new(Timing::new(140., 8));
+//! loop {
+//!     music.update();
+//!     while let Some(event) = time_source::poll_events(&mut rocket, &mut music).unwrap() {
+//!         // Do something with the various events.
+//!     }
+//!     let row = rocket.timing().row_at(music.position()) as f32;
+//!     println!("value: {}", rocket.get_value("test", row).unwrap());
+//!     # break;
+//! }
+//! ```
+use crate::client::Event;
+use crate::playback::{Clock, Playback};
+use crate::sync::SyncSource;
+use crate::timing::Timing;
+
+use std::time::Duration;
+
+/// A source of the current playback position, usually an audio player.
+pub trait TimeSource {
+    /// Get the current playback position.
+    fn position(&self) -> Duration;
+
+    /// Check if playback is paused.
+    fn is_paused(&self) -> bool;
+
+    /// Pause or resume playback.
+    fn set_paused(&mut self, paused: bool);
+
+    /// Jump to a playback position.
+    fn seek(&mut self, position: Duration);
+
+    /// Get the current row, see [`Timing::row_at`].
+    fn get_row(&self, timing: &Timing) -> f64 {
+        timing.row_at(self.position())
+    }
+}
+
+/// Poll for new events from `sync` like [`SyncSource::poll_events`], and keep `source` in step
+/// with it.
+///
+/// [`Event::SetRow`] seeks `source` unless it is already in that row, so a
+/// [`RocketPlayer`](crate::RocketPlayer) reporting the row set from `source` doesn't make it
+/// jump back. [`Event::Pause`] pauses or resumes `source`. When there are
+/// no more events, `source` follows the pause state of `sync`, and the row of `sync` is set from
+/// the position of `source` while playing. Rows and positions are converted with
+/// [`SyncSource::timing`].
+///
+/// # Errors
+///
+/// Any error from [`SyncSource::poll_events`] or [`SyncSource::set_row`].
+pub fn poll_events<S, T>(sync: &mut S, source: &mut T) -> Result<Option<Event>, S::Error>
+where
+    S: SyncSource + ?Sized,
+    T: TimeSource + ?Sized,
+{
+    let event = sync.poll_events()?;
+    let timing = sync.timing();
+    match event {
+        Some(Event::SetRow(row)) => {
+            if source.get_row(&timing) as u32 != row {
+                source.seek(timing.time_at(f64::from(row)));
+            }
+        }
+        Some(Event::Pause(paused)) => source.set_paused(paused),
+        Some(_) => (),
+        None => {
+            if source.is_paused() != sync.is_paused() {
+                source.set_paused(sync.is_paused());
+            }
+            if !source.is_paused() {
+                let row = source.get_row(&timing) as u32;
+                if row != sync.get_row() {
+                    sync.set_row(row)?;
+                }
+            }
+        }
+    }
+    Ok(event)
+}
+
+/// A [`Playback`] is a [`TimeSource`] for demos without music, or as a fallback. Its position
+/// only advances when [`Playback::update`] is called.
+impl<C: Clock> TimeSource for Playback<C> {
+    fn position(&self) -> Duration {
+        self.get_time()
+    }
+
+    fn is_paused(&self) -> bool {
+        Playback::is_paused(self)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        Playback::set_paused(self, paused);
+    }
+
+    fn seek(&mut self, position: Duration) {
+        self.seek_time(position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::ManualClock;
+    use crate::track::Track;
+    use crate::RocketPlayer;
+
+    #[test]
+    fn test_poll_events() {
+        let mut player = RocketPlayer::new(vec![Track::new("test")]);
+        player.set_timing(Timing::new(60., 4));
+        let mut source = Playback::with_clock(ManualClock::default(), Timing::new(60., 4));
+
+        // The player is not paused, so the source is resumed
+        assert!(poll_events(&mut player, &mut source).unwrap().is_none());
+        assert!(!source.is_paused());
+
+        source.clock_mut().advance(Duration::from_millis(2600));
+        source.update();
+        poll_events(&mut player, &mut source).unwrap();
+        assert_eq!(player.get_row(), 10);
+
+        player.pause(true);
+        assert!(matches!(
+            poll_events(&mut player, &mut source).unwrap(),
+            Some(Event::SetRow(10))
+        ));
+        assert_eq!(source.get_time(), Duration::from_millis(2600));
+        assert!(matches!(
+            poll_events(&mut player, &mut source).unwrap(),
+            Some(Event::Pause(true))
+        ));
+        assert!(source.is_paused());
+    }
+}
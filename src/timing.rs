@@ -0,0 +1,111 @@
+//! This module contains [`Timing`], which converts between rows and time.
+use core::time::Duration;
+
+/// The tempo of a demo, used to convert between rows and time.
+///
+/// Rocket itself only knows rows. With a `Timing`, a row is `60 / bpm / rows_per_beat` seconds
+/// long and row 0 starts at `offset`, which is useful when the music has silence before the
+/// first beat.
+///
+/// # Examples
+///
+/// ```
+/// # use rust_rocket::timing::Timing;
+/// use std::time::Duration;
+///
+/// let timing = Timing::new(120., 8).with_offset(Duration::from_secs(1));
+/// assert_eq!(timing.rows_per_second(), 16.);
+/// assert_eq!(timing.row_at(Duration::from_millis(1500)), 8.);
+/// assert_eq!(timing.time_at(8.), Duration::from_millis(1500));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    bpm: f64,
+    rows_per_beat: u32,
+    offset: Duration,
+}
+
+impl Timing {
+    /// Construct a new `Timing` without an offset.
+    ///
+    /// # Panics
+    ///
+    /// If `bpm` is not positive and finite, or `rows_per_beat` is 0.
+    pub fn new(bpm: f64, rows_per_beat: u32) -> Self {
+        assert!(bpm.is_finite() && bpm > 0., "bpm must be positive");
+        assert!(rows_per_beat > 0, "rows_per_beat must be positive");
+        Self {
+            bpm,
+            rows_per_beat,
+            offset: Duration::ZERO,
+        }
+    }
+
+    /// Set the time at which row 0 starts.
+    pub fn with_offset(mut self, offset: Duration) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Get the beats per minute.
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Get the number of rows per beat.
+    pub fn rows_per_beat(&self) -> u32 {
+        self.rows_per_beat
+    }
+
+    /// Get the time at which row 0 starts.
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// Get the number of rows per second.
+    pub fn rows_per_second(&self) -> f64 {
+        self.bpm / 60. * f64::from(self.rows_per_beat)
+    }
+
+    /// Convert a time to a row.
+    ///
+    /// The row is negative if `time` is before the offset.
+    pub fn row_at(&self, time: Duration) -> f64 {
+        (time.as_secs_f64() - self.offset.as_secs_f64()) * self.rows_per_second()
+    }
+
+    /// Convert a row to a time.
+    ///
+    /// Times before zero are clamped to zero.
+    pub fn time_at(&self, row: f64) -> Duration {
+        let seconds = row / self.rows_per_second() + self.offset.as_secs_f64();
+        Duration::try_from_secs_f64(seconds.max(0.)).unwrap_or(Duration::MAX)
+    }
+}
+
+/// The default timing is 120 BPM with 8 rows per beat, so that a row lasts 62.5 ms.
+impl Default for Timing {
+    fn default() -> Self {
+        Self::new(120., 8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let timing = Timing::new(150., 4).with_offset(Duration::from_millis(500));
+        assert_eq!(timing.rows_per_second(), 10.);
+        assert_eq!(timing.row_at(Duration::ZERO), -5.);
+        assert_eq!(timing.row_at(Duration::from_millis(750)), 2.5);
+        assert_eq!(timing.time_at(2.5), Duration::from_millis(750));
+        assert_eq!(timing.time_at(-100.), Duration::ZERO);
+
+        for row in 0..1000 {
+            let time = timing.time_at(f64::from(row));
+            assert!((timing.row_at(time) - f64::from(row)).abs() < 1e-6);
+        }
+    }
+}
@@ -1,34 +1,111 @@
-//! This module contains `Key` and `Track` types.
+//! This module contains `Key` and `Track` types. `Track` needs the `std` feature.
 
 use crate::interpolation::*;
+#[cfg(feature = "std")]
+use core::ops::{Bound, Range, RangeBounds};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 /// The `Key` Type.
 pub struct Key {
-    row: u32,
-    value: f32,
-    interpolation: Interpolation,
+    pub(crate) row: u32,
+    pub(crate) value: f32,
+    pub(crate) interpolation: Interpolation,
+    /// Slopes in value per row before and after the key, used by [`Interpolation::Bezier`].
+    /// Skipped by serde, so that the serialized layout stays compatible with older versions.
+    #[serde(skip)]
+    pub(crate) tangents: [f32; 2],
 }
 
 impl Key {
     /// Construct a new `Key`.
-    pub fn new(row: u32, value: f32, interp: Interpolation) -> Key {
+    pub const fn new(row: u32, value: f32, interp: Interpolation) -> Key {
         Key {
             row,
             value,
             interpolation: interp,
+            tangents: [0.0; 2],
         }
     }
+
+    /// Get the row of the key.
+    pub fn get_row(&self) -> u32 {
+        self.row
+    }
+
+    /// Get the value of the key.
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+
+    /// Get the interpolation from this key to the next key.
+    pub fn get_interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Get the incoming and outgoing tangents, see [`with_tangents`](Key::with_tangents).
+    pub fn get_tangents(&self) -> [f32; 2] {
+        self.tangents
+    }
+
+    /// Set the slopes of the curve in value per row when entering and leaving the key.
+    ///
+    /// The tangents shape [`Interpolation::Bezier`] segments which start or end at the key.
+    /// The default tangents are 0, which makes the curve flat at the key.
+    /// Tangents are not part of the Rocket protocol, so keys received from a tracker always
+    /// have the default tangents. They are also not serialized with serde, use
+    /// [`formats::bundle`](crate::formats::bundle) to save them.
+    pub const fn with_tangents(mut self, incoming: f32, outgoing: f32) -> Key {
+        self.tangents = [incoming, outgoing];
+        self
+    }
 }
 
+/// Evaluate a cubic Hermite curve between `p0` and `p1` with tangents `m0` and `m1`.
+fn hermite(p0: f32, m0: f32, p1: f32, m1: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * t3 - 3.0 * t2 + 1.0) * p0
+        + (t3 - 2.0 * t2 + t) * m0
+        + (-2.0 * t3 + 3.0 * t2) * p1
+        + (t3 - t2) * m1
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Default)]
+/// A block of keys copied from a track with [`Track::copy_rows`].
+pub struct Block {
+    len: u32,
+    keys: Vec<Key>,
+}
+
+#[cfg(feature = "std")]
+impl Block {
+    /// Get the number of rows in the block.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Check if the block has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the keys of the block. Rows are relative to the start of the block.
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+}
+
+#[cfg(feature = "std")]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 /// The `Track` Type. This is a collection of `Key`s with a name.
 pub struct Track {
     name: String,
-    keys: Vec<Key>,
+    pub(crate) keys: Vec<Key>,
 }
 
+#[cfg(feature = "std")]
 impl Track {
     /// Construct a new Track with a name.
     pub fn new<S: Into<String>>(name: S) -> Track {
@@ -43,30 +120,63 @@ impl Track {
         self.name.as_str()
     }
 
-    fn get_exact_position(&self, row: u32) -> Option<usize> {
-        self.keys.iter().position(|k| k.row == row)
+    /// Get all keys, sorted by row.
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
     }
 
-    fn get_insert_position(&self, row: u32) -> Option<usize> {
-        self.keys.iter().position(|k| k.row >= row)
+    /// Get the keys with rows in `rows`, sorted by row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rust_rocket::track::{Key, Track};
+    /// # use rust_rocket::interpolation::Interpolation;
+    /// let mut track = Track::new("test");
+    /// for row in [0, 5, 10, 15] {
+    ///     track.set_key(Key::new(row, 0., Interpolation::Step));
+    /// }
+    /// let rows: Vec<u32> = track.keys_in(5..15).iter().map(|k| k.get_row()).collect();
+    /// assert_eq!(rows, [5, 10]);
+    /// ```
+    pub fn keys_in<R: RangeBounds<u32>>(&self, rows: R) -> &[Key] {
+        let start = match rows.start_bound() {
+            Bound::Included(&row) => self.keys.partition_point(|k| k.row < row),
+            Bound::Excluded(&row) => self.keys.partition_point(|k| k.row <= row),
+            Bound::Unbounded => 0,
+        };
+        let end = match rows.end_bound() {
+            Bound::Included(&row) => self.keys.partition_point(|k| k.row <= row),
+            Bound::Excluded(&row) => self.keys.partition_point(|k| k.row < row),
+            Bound::Unbounded => self.keys.len(),
+        };
+        &self.keys[start..end.max(start)]
     }
 
-    fn get_lower_bound_position(&self, row: u32) -> usize {
-        self.keys
-            .iter()
-            .position(|k| k.row > row)
-            .unwrap_or(self.keys.len())
-            - 1
+    /// Get the key at exactly `row`, if any.
+    pub fn key_at(&self, row: u32) -> Option<&Key> {
+        self.get_exact_position(row).ok().map(|pos| &self.keys[pos])
+    }
+
+    /// Get the row of the first key, or `None` if the track has no keys.
+    pub fn first_row(&self) -> Option<u32> {
+        self.keys.first().map(|k| k.row)
+    }
+
+    /// Get the row of the last key, or `None` if the track has no keys.
+    pub fn last_row(&self) -> Option<u32> {
+        self.keys.last().map(|k| k.row)
+    }
+
+    fn get_exact_position(&self, row: u32) -> Result<usize, usize> {
+        self.keys.binary_search_by_key(&row, |k| k.row)
     }
 
     /// Insert or update a key on a track.
     pub fn set_key(&mut self, key: Key) {
-        if let Some(pos) = self.get_exact_position(key.row) {
-            self.keys[pos] = key;
-        } else if let Some(pos) = self.get_insert_position(key.row) {
-            self.keys.insert(pos, key);
-        } else {
-            self.keys.push(key);
+        match self.get_exact_position(key.row) {
+            Ok(pos) => self.keys[pos] = key,
+            Err(pos) => self.keys.insert(pos, key),
         }
     }
 
@@ -74,43 +184,314 @@ impl Track {
     ///
     /// If a key does not exist this will do nothing.
     pub fn delete_key(&mut self, row: u32) {
-        if let Some(pos) = self.get_exact_position(row) {
+        if let Ok(pos) = self.get_exact_position(row) {
             self.keys.remove(pos);
         }
     }
 
+    /// Replace the keys with `keys`. If several keys have the same row, the last one is kept.
+    fn replace_keys<I: IntoIterator<Item = Key>>(&mut self, keys: I) {
+        let mut keys: Vec<Key> = keys.into_iter().collect();
+        keys.sort_by_key(|k| k.row);
+        self.keys.clear();
+        for key in keys {
+            match self.keys.last_mut() {
+                Some(last) if last.row == key.row => *last = key,
+                _ => self.keys.push(key),
+            }
+        }
+    }
+
+    /// Insert `count` empty rows at `row`, moving later keys down.
+    ///
+    /// Keys which would be moved past `u32::MAX` are deleted.
+    pub fn insert_rows(&mut self, row: u32, count: u32) {
+        let keys = std::mem::take(&mut self.keys);
+        self.replace_keys(keys.into_iter().filter_map(|mut key| {
+            if key.row >= row {
+                key.row = key.row.checked_add(count)?;
+            }
+            Some(key)
+        }));
+    }
+
+    /// Delete the rows in `rows` and their keys, moving later keys up.
+    pub fn delete_rows(&mut self, rows: Range<u32>) {
+        let count = rows.end.saturating_sub(rows.start);
+        let keys = std::mem::take(&mut self.keys);
+        self.replace_keys(keys.into_iter().filter_map(|mut key| {
+            if rows.contains(&key.row) {
+                return None;
+            }
+            if key.row >= rows.end {
+                key.row -= count;
+            }
+            Some(key)
+        }));
+    }
+
+    /// Stretch or squash the keys in `rows` by `factor`, moving later keys accordingly.
+    ///
+    /// Rows are rounded to the nearest integer. If keys end up on the same row, the later one
+    /// is kept. Keys which would be moved past `u32::MAX` are deleted.
+    ///
+    /// # Panics
+    ///
+    /// If `factor` is not positive and finite.
+    pub fn scale_rows(&mut self, rows: Range<u32>, factor: f64) {
+        assert!(factor.is_finite() && factor > 0., "factor must be positive");
+        if rows.is_empty() {
+            return;
+        }
+        let scale = |offset: u32| (f64::from(offset) * factor).round();
+        let len = rows.end - rows.start;
+        let scaled_len = scale(len);
+
+        let keys = std::mem::take(&mut self.keys);
+        self.replace_keys(keys.into_iter().filter_map(|mut key| {
+            let row = if rows.contains(&key.row) {
+                f64::from(rows.start) + scale(key.row - rows.start)
+            } else if key.row >= rows.end {
+                f64::from(key.row) - f64::from(len) + scaled_len
+            } else {
+                f64::from(key.row)
+            };
+            key.row = if row <= f64::from(u32::MAX) {
+                row as u32
+            } else {
+                return None;
+            };
+            Some(key)
+        }));
+    }
+
+    /// Copy the keys in `rows`, see [`paste_rows`](Track::paste_rows).
+    pub fn copy_rows(&self, rows: Range<u32>) -> Block {
+        Block {
+            len: rows.end.saturating_sub(rows.start),
+            keys: self
+                .keys_in(rows.clone())
+                .iter()
+                .map(|&key| Key {
+                    row: key.row - rows.start,
+                    ..key
+                })
+                .collect(),
+        }
+    }
+
+    /// Replace the keys in the rows starting at `row` with the keys of `block`.
+    ///
+    /// Keys which would be pasted past `u32::MAX` are not pasted.
+    pub fn paste_rows(&mut self, row: u32, block: &Block) {
+        let rows = row..row.saturating_add(block.len);
+        let keys = std::mem::take(&mut self.keys);
+        let pasted = block.keys.iter().filter_map(|&key| {
+            Some(Key {
+                row: row.checked_add(key.row)?,
+                ..key
+            })
+        });
+        self.replace_keys(
+            keys.into_iter()
+                .filter(|key| !rows.contains(&key.row))
+                .chain(pasted),
+        );
+    }
+
+    /// Reverse the order of the rows in `rows`.
+    ///
+    /// Interpolations and tangents move with the segments between keys, so the curve is
+    /// mirrored. [`EaseIn`](Interpolation::EaseIn) and [`EaseOut`](Interpolation::EaseOut) are
+    /// swapped, other interpolation types have no mirrored counterpart and are kept.
+    pub fn reverse_rows(&mut self, rows: Range<u32>) {
+        let start = self.keys.partition_point(|k| k.row < rows.start);
+        let end = self.keys.partition_point(|k| k.row < rows.end);
+        let section = &mut self.keys[start..end];
+        if section.is_empty() {
+            return;
+        }
+
+        // The last key's interpolation leads out of the section, so it stays in place
+        let last = section.len() - 1;
+        let outgoing = section[last].interpolation;
+        for i in (1..=last).rev() {
+            section[i].interpolation = section[i - 1].interpolation;
+        }
+        section[0].interpolation = outgoing;
+        section.reverse();
+
+        for key in section.iter_mut() {
+            key.row = rows.start + (rows.end - 1 - key.row);
+            key.tangents = [-key.tangents[1], -key.tangents[0]];
+        }
+        for key in section[..last].iter_mut() {
+            key.interpolation = match key.interpolation {
+                Interpolation::EaseIn => Interpolation::EaseOut,
+                Interpolation::EaseOut => Interpolation::EaseIn,
+                interpolation => interpolation,
+            };
+        }
+    }
+
     /// Get a value based on a row.
     ///
     /// The row can be between two integers.
     /// This will perform the required interpolation.
+    /// [`Interpolation::CatmullRom`] also looks at the keys before and after the two keys
+    /// around the row.
+    ///
+    /// Rows before the first key have the value of the first key, and rows after the last key
+    /// have the value of the last key. Rows between the first two keys are interpolated like
+    /// all other rows.
+    ///
+    /// The keys around the row are found with a binary search. Use [`cursor`](Track::cursor)
+    /// when sampling rows in order.
     pub fn get_value(&self, row: f32) -> f32 {
-        if self.keys.is_empty() {
-            return 0.0;
+        sample(&self.keys, row)
+    }
+
+    /// Get a sampler which remembers the position of the last sampled key.
+    ///
+    /// Playback usually samples rows close to the previous one, so checking the previous
+    /// position first makes sampling amortized O(1).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rust_rocket::track::{Key, Track};
+    /// # use rust_rocket::interpolation::Interpolation;
+    /// let mut track = Track::new("test");
+    /// track.set_key(Key::new(0, 0., Interpolation::Linear));
+    /// track.set_key(Key::new(100, 1., Interpolation::Step));
+    ///
+    /// let mut cursor = track.cursor();
+    /// for row in 0..100 {
+    ///     assert_eq!(cursor.get_value(row as f32), track.get_value(row as f32));
+    /// }
+    /// ```
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor {
+            keys: &self.keys,
+            pos: 0,
         }
+    }
+}
 
-        let lower_row = row.floor() as u32;
+/// A sampler for a [`Track`] which remembers the position of the last sampled key, see
+/// [`Track::cursor`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    keys: &'a [Key],
+    pos: usize,
+}
 
-        if lower_row <= self.keys[0].row {
-            return self.keys[0].value;
-        }
+#[cfg(feature = "std")]
+impl Cursor<'_> {
+    /// Get a value based on a row, like [`Track::get_value`].
+    pub fn get_value(&mut self, row: f32) -> f32 {
+        let keys = self.keys;
+        let cursor = &mut self.pos;
+        sample_with(keys, row, |row| {
+            let contains = |pos: usize| keys[pos].row <= row && keys[pos + 1].row > row;
+            let pos = if *cursor + 1 < keys.len() && contains(*cursor) {
+                *cursor
+            } else if *cursor + 2 < keys.len() && contains(*cursor + 1) {
+                *cursor + 1
+            } else {
+                keys.partition_point(|k| k.row <= row) - 1
+            };
+            *cursor = pos;
+            pos
+        })
+    }
+}
 
-        if lower_row >= self.keys[self.keys.len() - 1].row {
-            return self.keys[self.keys.len() - 1].value;
-        }
+/// Get the value of `keys` at a row, like [`Track::get_value`].
+///
+/// This is useful for keys which are not in a [`Track`], like static arrays generated by
+/// [`codegen`](crate::codegen). `keys` must be sorted by row without duplicate rows.
+///
+/// # Examples
+///
+/// ```
+/// # use rust_rocket::interpolation::Interpolation;
+/// # use rust_rocket::track::{self, Key};
+/// static KEYS: &[Key] = &[
+///     Key::new(0, 0.0, Interpolation::Linear),
+///     Key::new(4, 1.0, Interpolation::Step),
+/// ];
+/// assert_eq!(track::sample(KEYS, 2.), 0.5);
+/// ```
+pub fn sample(keys: &[Key], row: f32) -> f32 {
+    sample_with(keys, row, |lower_row| {
+        keys.partition_point(|k| k.row <= lower_row) - 1
+    })
+}
 
-        let pos = self.get_lower_bound_position(lower_row);
+/// Get the slope in value per row at the key at `pos`, estimated from its neighbours.
+fn catmull_rom_slope(keys: &[Key], pos: usize) -> f32 {
+    let before = &keys[pos.saturating_sub(1)];
+    let after = &keys[(pos + 1).min(keys.len() - 1)];
+    if after.row == before.row {
+        return 0.0;
+    }
+    (after.value - before.value) / (after.row as f32 - before.row as f32)
+}
 
-        let lower = &self.keys[pos];
-        let higher = &self.keys[pos + 1];
+/// Get the value of `keys` at a row. `lower_bound` finds the position of the last key at or
+/// before a row, which must be at or after the first key and before the last key.
+fn sample_with<F: FnOnce(u32) -> usize>(keys: &[Key], row: f32, lower_bound: F) -> f32 {
+    if keys.is_empty() {
+        return 0.0;
+    }
 
-        let t = (row - (lower.row as f32)) / ((higher.row as f32) - (lower.row as f32));
-        let it = lower.interpolation.interpolate(t);
+    let first = &keys[0];
+    let last = &keys[keys.len() - 1];
 
-        (lower.value as f32) + ((higher.value as f32) - (lower.value as f32)) * it
+    if row.is_nan() || row < first.row as f32 {
+        return first.value;
+    }
+
+    // `row` is not negative or NaN here, so truncating is the same as `f32::floor`, which needs `std`
+    let lower_row = row as u32;
+
+    if lower_row >= last.row {
+        return last.value;
+    }
+
+    let pos = lower_bound(lower_row);
+
+    let lower = &keys[pos];
+    let higher = &keys[pos + 1];
+
+    let len = (higher.row as f32) - (lower.row as f32);
+    let t = (row - (lower.row as f32)) / len;
+
+    match lower.interpolation {
+        Interpolation::CatmullRom => hermite(
+            lower.value,
+            catmull_rom_slope(keys, pos) * len,
+            higher.value,
+            catmull_rom_slope(keys, pos + 1) * len,
+            t,
+        ),
+        Interpolation::Bezier => hermite(
+            lower.value,
+            lower.tangents[1] * len,
+            higher.value,
+            higher.tangents[0] * len,
+            t,
+        ),
+        interpolation => {
+            let it = interpolation.interpolate(t);
+            lower.value + (higher.value - lower.value) * it
+        }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -133,4 +514,142 @@ mod tests {
         assert_eq!(track.get_value(10.), 1.0);
         assert_eq!(track.get_value(11.), 1.0);
     }
+
+    #[test]
+    fn test_first_segment_interpolates() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(2, 0.0, Interpolation::Linear));
+        track.set_key(Key::new(4, 1.0, Interpolation::Step));
+
+        assert_eq!(track.get_value(1.5), 0.0);
+        assert_eq!(track.get_value(2.5), 0.25);
+        assert_eq!(track.get_value(3.), 0.5);
+    }
+
+    #[test]
+    fn test_inspection() {
+        let mut track = Track::new("test");
+        assert_eq!(track.first_row(), None);
+        assert!(track.keys_in(..).is_empty());
+
+        track.set_key(Key::new(4, 1.0, Interpolation::Smooth));
+        track.set_key(Key::new(8, 2.0, Interpolation::Step));
+        track.set_key(Key::new(2, 3.0, Interpolation::Linear));
+
+        assert_eq!(track.first_row(), Some(2));
+        assert_eq!(track.last_row(), Some(8));
+        assert_eq!(track.keys().len(), 3);
+        assert_eq!(track.keys_in(3..=8).len(), 2);
+        assert_eq!(track.keys_in(9..).len(), 0);
+        assert_eq!(track.keys_in(5..6).len(), 0);
+
+        let key = track.key_at(4).unwrap();
+        assert_eq!(key.get_row(), 4);
+        assert_eq!(key.get_value(), 1.0);
+        assert_eq!(key.get_interpolation(), Interpolation::Smooth);
+        assert!(track.key_at(5).is_none());
+    }
+
+    fn rows(track: &Track) -> Vec<u32> {
+        track.keys().iter().map(|k| k.row).collect()
+    }
+
+    #[test]
+    fn test_bulk_edits() {
+        let mut track = Track::new("test");
+        for row in [0, 4, 8, 12] {
+            track.set_key(Key::new(row, row as f32, Interpolation::Linear));
+        }
+
+        track.insert_rows(4, 2);
+        assert_eq!(rows(&track), [0, 6, 10, 14]);
+        track.delete_rows(5..10);
+        assert_eq!(rows(&track), [0, 5, 9]);
+        track.scale_rows(0..5, 2.);
+        assert_eq!(rows(&track), [0, 10, 14]);
+        track.scale_rows(0..10, 0.1);
+        assert_eq!(rows(&track), [0, 1, 5]);
+        assert_eq!(track.get_value(1.), 8.);
+
+        let block = track.copy_rows(1..4);
+        assert_eq!(block.len(), 3);
+        assert_eq!(block.keys()[0].row, 0);
+        track.paste_rows(4, &block);
+        assert_eq!(rows(&track), [0, 1, 4]);
+        assert_eq!(track.get_value(5.), 8.);
+    }
+
+    #[test]
+    fn test_reverse_rows() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(0, 0.0, Interpolation::Step));
+        track.set_key(Key::new(2, 2.0, Interpolation::Linear));
+        track.set_key(Key::new(6, 6.0, Interpolation::EaseIn));
+        track.set_key(Key::new(8, 8.0, Interpolation::Step));
+        let values: Vec<f32> = (2..=6).map(|row| track.get_value(row as f32)).collect();
+
+        track.reverse_rows(2..7);
+        assert_eq!(rows(&track), [0, 2, 6, 8]);
+        let reversed: Vec<f32> = (2..=6)
+            .rev()
+            .map(|row| track.get_value(row as f32))
+            .collect();
+        assert_eq!(values, reversed);
+        assert_eq!(track.keys()[2].interpolation, Interpolation::EaseIn);
+    }
+
+    #[test]
+    fn test_curves() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(0, 0.0, Interpolation::CatmullRom));
+        track.set_key(Key::new(10, 10.0, Interpolation::CatmullRom));
+        track.set_key(Key::new(20, 20.0, Interpolation::Bezier).with_tangents(1.0, 1.0));
+        track.set_key(Key::new(30, 30.0, Interpolation::Step).with_tangents(1.0, 0.0));
+
+        // Collinear keys make straight curves
+        for row in 0..30 {
+            assert!((track.get_value(row as f32) - row as f32).abs() < 1e-4);
+        }
+
+        track.set_key(Key::new(30, 40.0, Interpolation::Step).with_tangents(0.0, 0.0));
+        assert!(track.get_value(25.) > 25.);
+        assert!(track.get_value(29.) < 40.);
+    }
+
+    #[test]
+    fn test_serde_layout() {
+        // Row, value and interpolation variant, like before tangents were added
+        let key = Key::new(3, 0.5, Interpolation::Bezier).with_tangents(1.0, 2.0);
+        let bytes = bincode::serialize(&key).unwrap();
+        assert_eq!(bytes.len(), 12);
+        let key: Key = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(key, Key::new(3, 0.5, Interpolation::Bezier));
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut track = Track::new("test");
+        for row in (0..100).step_by(10) {
+            track.set_key(Key::new(row, row as f32, Interpolation::Linear));
+        }
+
+        // Forwards, backwards and jumping around must all match the keys
+        let mut cursor = track.cursor();
+        let rows = (0..=90).chain((0..=90).rev()).chain([50, 3, 89, 10, 9, 0]);
+        for row in rows {
+            assert_eq!(cursor.get_value(row as f32), row as f32);
+        }
+        assert_eq!(cursor.get_value(-5.), 0.);
+        assert_eq!(cursor.get_value(95.), 90.);
+    }
+
+    #[test]
+    fn test_nan() {
+        let mut track = Track::new("test");
+        track.set_key(Key::new(10, 1.0, Interpolation::Linear));
+        track.set_key(Key::new(20, 2.0, Interpolation::Step));
+
+        assert_eq!(track.get_value(f32::NAN), 1.0);
+        assert_eq!(track.cursor().get_value(f32::NAN), 1.0);
+    }
 }
@@ -0,0 +1,37 @@
+//! This module contains the [`Transport`] trait, which abstracts the stream a
+//! [`RocketClient`](crate::RocketClient) talks to the tracker over.
+use std::io::prelude::*;
+use std::net::TcpStream;
+
+/// A bidirectional byte stream that can carry the Rocket protocol.
+///
+/// The client performs the handshake in blocking mode and then switches the transport to
+/// nonblocking mode with [`set_nonblocking`](Transport::set_nonblocking).
+/// In nonblocking mode, reads must fail with [`std::io::ErrorKind::WouldBlock`] when no data is
+/// available instead of waiting for more.
+///
+/// Implementations are provided for [`TcpStream`] and, on unix platforms,
+/// [`UnixStream`](std::os::unix::net::UnixStream).
+pub trait Transport: Read + Write {
+    /// Switch the transport to or from nonblocking mode.
+    fn set_nonblocking(&mut self, nonblocking: bool) -> std::io::Result<()>;
+}
+
+impl Transport for TcpStream {
+    fn set_nonblocking(&mut self, nonblocking: bool) -> std::io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl Transport for std::os::unix::net::UnixStream {
+    fn set_nonblocking(&mut self, nonblocking: bool) -> std::io::Result<()> {
+        std::os::unix::net::UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn set_nonblocking(&mut self, nonblocking: bool) -> std::io::Result<()> {
+        (**self).set_nonblocking(nonblocking)
+    }
+}
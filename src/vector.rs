@@ -0,0 +1,157 @@
+//! This module contains [`VectorTrack`], which groups component tracks into one vector value.
+//!
+//! Rocket only has scalar tracks, so vectors and colors are made of one track per component.
+//! By convention, component tracks are named `<base>.<component>`, for example `cam:pos.x`,
+//! `cam:pos.y` and `cam:pos.z`. The Rocket editor groups tracks by the part of the name before
+//! `:`, so the components of a vector are shown next to each other.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # use rust_rocket::{RocketClient, SyncSource};
+//! # use rust_rocket::vector::VectorTrack;
+//! let mut rocket = RocketClient::new().unwrap();
+//! let position = VectorTrack::vec3("cam:pos");
+//! position.request(&mut rocket).unwrap();
+//! let [x, y, z] = position.get_value(&mut rocket, 12.5).unwrap();
+//! ```
+use crate::sync::SyncSource;
+
+/// Get the name of a component track, `<base>.<component>`.
+pub fn component_name(base: &str, component: &str) -> String {
+    format!("{}.{}", base, component)
+}
+
+/// Split the name of a component track into the base name and the component.
+///
+/// Returns `None` if `name` doesn't contain `.`.
+///
+/// # Examples
+///
+/// ```
+/// # use rust_rocket::vector::split_component;
+/// assert_eq!(split_component("cam:pos.x"), Some(("cam:pos", "x")));
+/// assert_eq!(split_component("cam:fov"), None);
+/// ```
+pub fn split_component(name: &str) -> Option<(&str, &str)> {
+    name.rsplit_once('.')
+}
+
+/// A handle to `N` component tracks which are sampled together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorTrack<const N: usize> {
+    names: [String; N],
+}
+
+impl<const N: usize> VectorTrack<N> {
+    /// Construct a new `VectorTrack` from the names of the component tracks.
+    pub fn new(names: [String; N]) -> Self {
+        Self { names }
+    }
+
+    /// Construct a new `VectorTrack` with tracks named `<base>.<component>`.
+    pub fn with_components(base: &str, components: [&str; N]) -> Self {
+        Self::new(components.map(|component| component_name(base, component)))
+    }
+
+    /// Get the names of the component tracks.
+    pub fn names(&self) -> &[String; N] {
+        &self.names
+    }
+
+    /// Request all component tracks from `sync`, see [`SyncSource::get_track`].
+    ///
+    /// Clients request tracks the first time they are sampled anyway, but requesting them
+    /// up front avoids the round trip during the first frame.
+    ///
+    /// # Errors
+    ///
+    /// The first error from [`SyncSource::get_track`].
+    pub fn request<S: SyncSource + ?Sized>(&self, sync: &mut S) -> Result<(), S::Error> {
+        for name in &self.names {
+            sync.get_track(name)?;
+        }
+        Ok(())
+    }
+
+    /// Get the values of all component tracks at a row, see [`SyncSource::get_value`].
+    ///
+    /// # Errors
+    ///
+    /// The first error from [`SyncSource::get_value`].
+    pub fn get_value<S: SyncSource + ?Sized>(
+        &self,
+        sync: &mut S,
+        row: f32,
+    ) -> Result<[f32; N], S::Error> {
+        let mut values = [0.; N];
+        for (value, name) in values.iter_mut().zip(&self.names) {
+            *value = sync.get_value(name, row)?;
+        }
+        Ok(values)
+    }
+}
+
+impl VectorTrack<2> {
+    /// Construct a new `VectorTrack` with components `x` and `y`.
+    pub fn vec2(base: &str) -> Self {
+        Self::with_components(base, ["x", "y"])
+    }
+}
+
+impl VectorTrack<3> {
+    /// Construct a new `VectorTrack` with components `x`, `y` and `z`.
+    pub fn vec3(base: &str) -> Self {
+        Self::with_components(base, ["x", "y", "z"])
+    }
+
+    /// Construct a new `VectorTrack` with components `r`, `g` and `b`.
+    pub fn rgb(base: &str) -> Self {
+        Self::with_components(base, ["r", "g", "b"])
+    }
+}
+
+impl VectorTrack<4> {
+    /// Construct a new `VectorTrack` with components `x`, `y`, `z` and `w`.
+    pub fn vec4(base: &str) -> Self {
+        Self::with_components(base, ["x", "y", "z", "w"])
+    }
+
+    /// Construct a new `VectorTrack` with components `r`, `g`, `b` and `a`.
+    pub fn rgba(base: &str) -> Self {
+        Self::with_components(base, ["r", "g", "b", "a"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+    use crate::player::{self, RocketPlayer};
+    use crate::track::{Key, Track};
+
+    #[test]
+    fn test_player() {
+        let tracks = ["cam:pos.x", "cam:pos.y", "cam:pos.z"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut track = Track::new(*name);
+                track.set_key(Key::new(0, 0., Interpolation::Linear));
+                track.set_key(Key::new(10, i as f32, Interpolation::Step));
+                track
+            })
+            .collect();
+        let mut player = RocketPlayer::new(tracks);
+
+        let position = VectorTrack::vec3("cam:pos");
+        position.request(&mut player).unwrap();
+        assert_eq!(position.get_value(&mut player, 5.).unwrap(), [0., 0.5, 1.]);
+
+        let color = VectorTrack::rgba("cam:pos");
+        assert!(matches!(
+            color.request(&mut player),
+            Err(player::Error::TrackNotFound(name)) if name == "cam:pos.r"
+        ));
+    }
+}
@@ -0,0 +1,32 @@
+//! Compiles a module generated by `codegen` for track names which could clash with the names
+//! the module uses itself.
+use rust_rocket::codegen;
+use rust_rocket::interpolation::Interpolation;
+use rust_rocket::track::{Key, Track};
+
+#[allow(dead_code)]
+mod sync {
+    include!("codegen/sync.rs");
+}
+
+fn tracks() -> Vec<Track> {
+    let mut sample = Track::new("sample");
+    sample.set_key(Key::new(0, 0.0, Interpolation::Linear));
+    sample.set_key(Key::new(4, 1.0, Interpolation::Step));
+    vec![sample, Track::new("tracks"), Track::new("key")]
+}
+
+#[test]
+fn test_generated_module() {
+    // Regenerate with `generate` if the output changes
+    assert_eq!(
+        codegen::generate(&tracks()),
+        include_str!("codegen/sync.rs")
+    );
+
+    assert_eq!(sync::sample(2.), 0.5);
+    assert_eq!(sync::tracks_(0.), 0.);
+    assert_eq!(sync::key(0.), 0.);
+    let names: Vec<&str> = sync::TRACKS.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["key", "sample", "tracks"]);
+}
@@ -0,0 +1,40 @@
+// Generated by rust-rocket. Do not edit.
+
+#[allow(unused_imports)]
+use rust_rocket::{interpolation::Interpolation, track::Key};
+
+// Keys of the track "key".
+pub static KEY: &[Key] = &[
+];
+
+// Get the value of the track "key" at a row.
+pub fn key(row: f32) -> f32 {
+    ::rust_rocket::track::sample(KEY, row)
+}
+
+// Keys of the track "sample".
+pub static SAMPLE: &[Key] = &[
+    Key::new(0, 0.0, Interpolation::Linear),
+    Key::new(4, 1.0, Interpolation::Step),
+];
+
+// Get the value of the track "sample" at a row.
+pub fn sample(row: f32) -> f32 {
+    ::rust_rocket::track::sample(SAMPLE, row)
+}
+
+// Keys of the track "tracks".
+pub static TRACKS_: &[Key] = &[
+];
+
+// Get the value of the track "tracks" at a row.
+pub fn tracks_(row: f32) -> f32 {
+    ::rust_rocket::track::sample(TRACKS_, row)
+}
+
+// All tracks by name, sorted by name.
+pub static TRACKS: &[(&str, &[Key])] = &[
+    ("key", KEY),
+    ("sample", SAMPLE),
+    ("tracks", TRACKS_),
+];